@@ -1,248 +1,1284 @@
-use rand;
-
 use std::fmt;
 use std::os::raw::c_void;
 use std::ptr::null_mut;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use rand::Rng;
+use rand::seq::SliceRandom;
 
+use crate::cage::{self, Cage, Combinations};
+use crate::canonical;
 use crate::cell::*;
+use crate::clock::{Clock, TimeSource};
+use crate::daily::{self, Date};
+use crate::difficulty::Difficulty;
+use crate::job::Progress;
+use crate::region;
+use crate::save::SaveData;
+use crate::score::{GameStats, ScoreConfig};
+use crate::solver::Solver;
+use crate::strategy::{Grid, Step, Technique};
+use crate::trace::SolveTrace;
+use crate::training::{GenerateError, TechniqueGoal};
+use crate::transform::Transform;
+use crate::units::Units;
+use crate::variant::Variant;
+
+// 生成终盘时随机重新搜索的次数
+const SOLUTION_ATTEMPTS: usize = 20;
+
+// 按提示数挖空时, 同一个终盘尝试的挖空顺序数
+const REMOVAL_ATTEMPTS: usize = 5;
+
+// 精确检查唯一解的搜索节点上限, 9x9 的题目远远达不到
+const EXACT_NODE_LIMIT: usize = 1_000_000;
+
+// 生成时挖空的方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Removal {
+    // 按难度挖去固定数量
+    Difficulty,
+    // 挖到每个提示数都不可缺少
+    Minimal,
+    // 挖到只剩指定数量的提示数, 不能保证达到
+    Clues(usize),
+}
+
+// 撤销记录中的格子和数字跟随盘面变换
+fn transform_action(action: Action, t: &Transform) -> Action {
+    match action {
+        Action::Value { index, old } => Action::Value { index: t.target(index), old: old.map(|v| t.digit(v)) },
+        Action::Notes { index, old } => Action::Notes { index: t.target(index), old: t.mask(old) },
+        Action::Batch(actions) => Action::Batch(actions.into_iter().map(|a| transform_action(a, t)).collect()),
+    }
+}
+
+// 宫的形状 (行数, 列数), 尽量接近正方形, 质数边长不支持
+pub fn box_shape(size: usize) -> Option<(usize, usize)> {
+    if size > MAX_SIZE {
+        return None;
+    }
+
+    (2..=size).take_while(|r| r * r <= size)
+        .filter(|r| size.is_multiple_of(*r))
+        .last()
+        .map(|r| (r, size / r))
+}
+
+// 可以撤销的玩家操作, 记录修改前的状态
+#[derive(Clone, Debug)]
+enum Action {
+    Value { index: usize, old: Option<u8> },
+    Notes { index: usize, old: u32 },
+    // 一次撤销的多个操作
+    Batch(Vec<Action>),
+}
+
+// 宿主传入的回调数据. 回调只在调用 Board 方法的线程上同步执行,
+// 由宿主保证数据可以在这些线程上使用
+#[derive(Clone, Copy)]
+pub(crate) struct CallbackPtr(pub(crate) *mut c_void);
 
+unsafe impl Send for CallbackPtr {}
+
+// Board 可以在线程间移动 (Send), 但不能同时从多个线程访问
 pub struct Board {
+    size: usize,
+    box_rows: usize,
+    box_columns: usize,
     numbers: Vec<Cell>,
+    variant: Variant,
+    difficulty: Difficulty,
+    // 由种子确定的题目 (如每日题目) 才有
+    seed: Option<u64>,
+    units: Arc<Units>,
+    regions: Vec<u8>,
+    cages: Vec<Cage>,
+    cage_of: Vec<Option<usize>>,
+    // 笼子的数字组合表, 只和边长有关, 第一次设置笼子时建立
+    combinations: Option<Combinations>,
     current_highlight: Option<u8>,
-    callback_ptr: *mut c_void,
-    update_callback: Option<extern fn(*mut c_void, u32, u32)>,
+    highlight_mode: HighlightMode,
+    // 选中的格子
+    selected_cell: Option<usize>,
+    clock: Clock,
+    // 唯一解, 题目有多解时为空
+    solution: Vec<u8>,
+    history: Vec<Action>,
+    stats: GameStats,
+    score_config: ScoreConfig,
+    callback_ptr: CallbackPtr,
+    update_callback: Option<extern "C" fn(*mut c_void, u32, u32)>,
+    // 批量操作期间待通知的格子
+    pending_updates: Option<Vec<usize>>,
 }
 
 impl Board {
     pub fn empty() -> Self {
-        Self {
-            numbers: (0..81).map(|_| Cell::new()).collect(),
+        Self::new(Variant::Standard)
+    }
+
+    pub fn new(variant: Variant) -> Self {
+        Self::with_shape(3, 3, variant).unwrap()
+    }
+
+    // 4x4, 6x6, 12x12, 16x16 等, 宫的形状由 box_shape 决定
+    pub fn with_size(size: usize, variant: Variant) -> Option<Self> {
+        box_shape(size).and_then(|(r, c)| Self::with_shape(r, c, variant))
+    }
+
+    pub fn with_shape(box_rows: usize, box_columns: usize, variant: Variant) -> Option<Self> {
+        let size = box_rows * box_columns;
+        if box_rows == 0 || box_columns == 0 || size > MAX_SIZE {
+            return None;
+        }
+
+        Some(Self {
+            size,
+            box_rows,
+            box_columns,
+            numbers: (0..size * size).map(|_| Cell::new(size)).collect(),
+            variant,
+            difficulty: Difficulty::default(),
+            seed: None,
+            units: variant.units(size, box_rows, box_columns),
+            regions: region::box_regions(size, box_rows, box_columns),
+            cages: vec![],
+            cage_of: vec![None; size * size],
+            combinations: None,
             current_highlight: None,
-            callback_ptr: null_mut(),
+            highlight_mode: HighlightMode::None,
+            selected_cell: None,
+            clock: Clock::default(),
+            solution: vec![],
+            history: vec![],
+            stats: GameStats::default(),
+            score_config: ScoreConfig::default(),
+            callback_ptr: CallbackPtr(null_mut()),
             update_callback: None,
+            pending_updates: None,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn box_shape(&self) -> (usize, usize) {
+        (self.box_rows, self.box_columns)
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.size * self.size
+    }
+
+    fn blank_cells(&self) -> Vec<Cell> {
+        (0..self.cell_count()).map(|_| Cell::new(self.size)).collect()
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    // 下一次 generate 使用的难度
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // 每日题目, 同一日期和难度在任何设备上都相同
+    pub fn daily(date: Date, difficulty: Difficulty) -> Self {
+        Self::seeded(daily::daily_seed(date, difficulty), difficulty)
+    }
+
+    // 由种子确定的标准 9x9 题目, 与每日题目使用相同的算法
+    pub fn seeded(seed: u64, difficulty: Difficulty) -> Self {
+        let puzzle: String = daily::daily_puzzle(seed, difficulty).into_iter()
+            .map(|v| if v == 0 { '.' } else { digit_to_char(v) })
+            .collect();
+
+        let mut board = Self::empty();
+        board.difficulty = difficulty;
+        board.load(&puzzle);
+        board.seed = Some(seed);
+
+        board
+    }
+
+    pub fn units(&self) -> &Units {
+        &self.units
+    }
+
+    pub fn regions(&self) -> &[u8] {
+        &self.regions
+    }
+
+    pub fn region(&self, row: usize, column: usize) -> u8 {
+        self.regions[row * self.size + column]
+    }
+
+    // 设置不规则区域并清空盘面, 只对 Jigsaw 有效
+    pub fn set_regions(&mut self, regions: Vec<u8>) -> bool {
+        if self.variant != Variant::Jigsaw || !region::is_valid_layout(&regions, self.size) {
+            return false;
         }
+
+        self.units = Arc::new(Units::from_regions(self.size, &regions));
+        self.regions = regions;
+        self.numbers = self.blank_cells();
+        self.emit_update_all();
+
+        true
     }
 
-    pub fn set_current_highlight(&mut self, high_light: Option<u8>) {
-        if self.current_highlight == high_light {
-            return;
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    pub fn cage_of(&self, row: usize, column: usize) -> Option<usize> {
+        self.cage_of[row * self.size + column]
+    }
+
+    fn build_combinations(&mut self) {
+        if self.combinations.is_none() {
+            self.combinations = Some(Combinations::new(self.size));
         }
+    }
 
-        let mut updates = vec![];
-        for (idx, cell) in self.numbers.iter_mut().enumerate() {
-            if self.current_highlight.is_some() {
-                if self.current_highlight == cell.selected() {
-                    cell.set_states(cell.states() & !CellStates::HIGH_LIGHT);
-                    updates.push(idx);
-                }
+    // 设置笼子并清空盘面, 只对 Killer 有效
+    pub fn set_cages(&mut self, cages: Vec<Cage>) -> bool {
+        if self.variant != Variant::Killer || !cage::is_valid_cages(&cages, self.size) {
+            return false;
+        }
+
+        self.cage_of = cage::cage_map(&cages, self.cell_count());
+        self.cages = cages;
+        self.build_combinations();
+        self.numbers = self.blank_cells();
+        self.emit_update_all();
+
+        true
+    }
+
+    // 替换计时用的时间源, 已经累计的时间保留
+    pub fn set_time_source(&mut self, source: Box<dyn TimeSource>) {
+        self.clock.set_source(source);
+    }
+
+    pub fn pause(&mut self) {
+        self.clock.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.clock.resume();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.clock.is_paused()
+    }
+
+    // 本局用时, 不含暂停的时间
+    pub fn elapsed_ms(&self) -> u64 {
+        self.clock.elapsed_ms()
+    }
+
+    pub fn set_score_config(&mut self, config: ScoreConfig) {
+        self.score_config = config;
+    }
+
+    pub fn score_config(&self) -> ScoreConfig {
+        self.score_config
+    }
+
+    // 本局统计, 分数按当前用时计算
+    pub fn stats(&self) -> GameStats {
+        let mut stats = self.stats;
+        stats.elapsed_ms = self.elapsed_ms();
+        stats.score = self.score_config.score(&stats);
+
+        stats
+    }
+
+    pub fn solution(&self) -> Option<&[u8]> {
+        (!self.solution.is_empty()).then_some(&self.solution[..])
+    }
+
+    // 新的一局: 清空历史和统计并开始计时
+    fn start_game(&mut self, solution: Vec<u8>) {
+        self.solution = solution;
+        self.seed = None;
+        self.history.clear();
+        self.stats = GameStats::default();
+        self.clock.start();
+    }
+
+    // 行列宫的候选数再按所在笼子的数字组合过滤
+    pub fn candidates(&self, row: usize, column: usize) -> u32 {
+        self.candidates_with(row * self.size + column, &self.values())
+    }
+
+    // 批量计算时由调用者准备好当前数字
+    fn candidates_with(&self, index: usize, values: &[u8]) -> u32 {
+        let mut flags = self.numbers[index].candidate_u32();
+
+        if let (Some(cage), Some(combinations)) = (self.cage_of[index], &self.combinations) {
+            flags &= self.cages[cage].allowed_digits(combinations, values);
+        }
+
+        flags
+    }
+
+    // 当前盘面的候选数网格, 用于人工解题技巧
+    pub fn candidate_grid(&self) -> Grid {
+        let values = self.values();
+        let mut grid = Grid::new(self.units.clone(), &values);
+        for cell in 0..self.cell_count() {
+            grid.set_candidates(cell, grid.candidates(cell) & self.candidates_with(cell, &values));
+        }
+        // 笼子的和限制了交换, 唯一解类技巧不成立
+        if !self.cages.is_empty() {
+            grid.set_uniqueness(false);
+        }
+
+        grid
+    }
+
+    // 在当前盘面上找一处可以使用 technique 的地方
+    pub fn find_step(&self, technique: Technique) -> Option<Step> {
+        technique.find(&self.candidate_grid())
+    }
+
+    // 只用人工技巧解题, 记录每一步
+    pub fn solve_logically(&self) -> SolveTrace {
+        SolveTrace::solve(self.candidate_grid(), &Technique::ALL)
+    }
+
+    // 把一步推理应用到盘面上, 不计入统计和撤销历史. 步骤超出盘面范围时不做修改并返回 false
+    pub fn apply_step(&mut self, step: &Step) -> bool {
+        if !step.fits(self.size) {
+            return false;
+        }
+
+        for e in step.eliminations.iter() {
+            let cell = &mut self.numbers[e.cell];
+            cell.set_excluded(cell.excluded() | (1 << e.digit));
+            self.emit_update_cell(e.cell / self.size, e.cell % self.size);
+        }
+        let eliminated: Vec<usize> = step.eliminations.iter().map(|e| e.cell).collect();
+        self.refresh_highlight_cells(&eliminated);
+
+        for p in step.placements.iter() {
+            self.set(p.cell / self.size, p.cell % self.size, Some(p.digit));
+        }
+
+        true
+    }
+
+    fn update_cage_conflict(&mut self, cage: usize) {
+        let values = self.values();
+        let broken = self.cages[cage].is_broken(&values);
+
+        for &idx in self.cages[cage].cells() {
+            let cell = &mut self.numbers[idx];
+            if broken {
+                cell.set_states(cell.states() | CellStates::CONFLICT);
+            } else {
+                cell.set_states(cell.states() & !CellStates::CONFLICT);
+            }
+        }
+    }
+
+    // 生成无提示数的笼子划分, 有多解时拆分出现分歧的笼子. 取消时返回 false
+    fn random_cages(&mut self, progress: &Progress) -> bool {
+        let solution = self.values();
+        let blank = vec![0; self.cell_count()];
+        let mut rng = rand::thread_rng();
+        let mut cages = cage::random_cages(&mut rng, &solution, self.size);
+
+        loop {
+            let mut solver = Solver::with_cages(&self.units, &cages, &blank).unwrap().node_limit(100_000).cancel_on(progress);
+            let count = solver.count_solutions(2);
+            if count == 1 && !solver.aborted() {
+                break;
+            }
+            if progress.is_cancelled() {
+                return false;
             }
 
-            if high_light.is_some() {
-                if high_light == cell.selected() {
-                    cell.set_states(cell.states() | CellStates::HIGH_LIGHT);
-                    updates.push(idx);
+            let differ = solver.solutions().iter()
+                .find_map(|s| (0..s.len()).find(|&i| s[i] != solution[i]));
+            let cell = match differ {
+                Some(cell) => cell,
+                // 搜索超时, 拆分最大的笼子
+                None => {
+                    let largest = cages.iter().max_by_key(|c| c.cells().len()).unwrap();
+                    *largest.cells().choose(&mut rng).unwrap()
                 }
+            };
+
+            cage::split_cage(&mut cages, cell, &solution, self.size);
+        }
+
+        self.cage_of = cage::cage_map(&cages, self.cell_count());
+        self.cages = cages;
+        self.build_combinations();
+        for cell in self.numbers.iter_mut() {
+            cell.set_select(None);
+        }
+
+        true
+    }
+
+    // 随机划分区域并求出一个解, 找不到时返回 None
+    fn random_regions(&mut self) -> Option<Vec<u8>> {
+        let mut rng = rand::thread_rng();
+        let blank = vec![0; self.cell_count()];
+        for _ in 0..20 {
+            let regions = region::random_layout(&mut rng, self.size, self.box_rows, self.box_columns, None);
+            let units = Units::from_regions(self.size, &regions);
+            let solution = Solver::new(&units, &blank)
+                .and_then(|s| s.shuffle(&mut rng).node_limit(self.cell_count() * 60).solve());
+
+            if let Some(solution) = solution {
+                self.units = Arc::new(units);
+                self.regions = regions;
+                return Some(solution);
+            }
+        }
+
+        // 大盘面的随机布局很难找到解, 改为在标准宫的解上调整区域
+        self.units = Variant::Standard.units(self.size, self.box_rows, self.box_columns);
+        let solution = self.random_solution()?;
+        let regions = region::random_layout(&mut rng, self.size, self.box_rows, self.box_columns, Some(&solution));
+        self.units = Arc::new(Units::from_regions(self.size, &regions));
+        self.regions = regions;
+
+        Some(solution)
+    }
+
+    // 随机终盘. 每次搜索限制节点数, 走进死胡同就换随机序列重来,
+    // 每几次把限制加倍. 全部失败时返回 None
+    fn random_solution(&self) -> Option<Vec<u8>> {
+        let mut rng = rand::thread_rng();
+        let blank = vec![0; self.cell_count()];
+        for attempt in 0..SOLUTION_ATTEMPTS {
+            let limit = (self.cell_count() * 20) << (attempt / 5);
+            let solution = Solver::new(&self.units, &blank)
+                .and_then(|s| s.node_limit(limit).solve_random(&mut rng));
+            if solution.is_some() {
+                return solution;
             }
+            trace!("Random solution attempt {} aborted", attempt);
         }
 
-        for i in updates {
-            self.emit_update_cell(i / 9, i % 9);
+        debug!("Failed to find a random solution in {} attempts", SOLUTION_ATTEMPTS);
+        None
+    }
+
+    fn fill(&mut self, values: &[u8]) {
+        self.numbers = self.blank_cells();
+        for (idx, &val) in values.iter().enumerate() {
+            if val != 0 {
+                self.set(idx / self.size, idx % self.size, Some(val));
+            }
         }
+    }
+
+    // 高亮填了 high_light 的格子, None 时取消高亮
+    pub fn set_current_highlight(&mut self, high_light: Option<u8>) {
+        let mode = if high_light.is_some() { HighlightMode::Digit } else { HighlightMode::None };
+        self.set_highlight(mode, high_light);
+    }
+
+    // Digit 和 Candidate 模式需要 digit, Peers 模式使用选中的格子
+    pub fn set_highlight(&mut self, mode: HighlightMode, digit: Option<u8>) {
+        self.highlight_mode = mode;
+        self.current_highlight = digit.filter(|&d| d > 0 && d as usize <= self.size);
+        self.refresh_highlight();
+    }
+
+    pub fn highlight_mode(&self) -> HighlightMode {
+        self.highlight_mode
+    }
+
+    pub fn highlight_digit(&self) -> Option<u8> {
+        self.current_highlight
+    }
+
+    // 选中格子, None 时取消选中
+    pub fn select(&mut self, cell: Option<(usize, usize)>) {
+        self.selected_cell = cell
+            .filter(|&(row, column)| row < self.size && column < self.size)
+            .map(|(row, column)| row * self.size + column);
+        self.refresh_highlight();
+    }
 
-        self.current_highlight = high_light;
+    pub fn selected_cell(&self) -> Option<(usize, usize)> {
+        self.selected_cell.map(|index| (index / self.size, index % self.size))
     }
 
-    pub fn set_update_callback(&mut self, cb: extern fn(*mut c_void, u32, u32)) {
+    // 重新计算所有格子的选中和高亮状态, 只通知状态有变化的格子
+    fn refresh_highlight(&mut self) {
+        let cells: Vec<usize> = (0..self.cell_count()).collect();
+        self.update_highlight(&cells);
+    }
+
+    // 格子的数字、笔记或候选数改变后只更新受影响的格子.
+    // 没有选中格子也没有高亮时所有格子都没有这些状态, 不需要检查
+    fn refresh_highlight_cells(&mut self, cells: &[usize]) {
+        let active = self.selected_cell.is_some()
+            || (self.current_highlight.is_some() && self.highlight_mode != HighlightMode::None);
+        if active {
+            self.update_highlight(cells);
+        }
+    }
+
+    fn update_highlight(&mut self, cells: &[usize]) {
+        let mask = CellStates::SELECTED | CellStates::HIGH_LIGHT | CellStates::CHECKING;
+        let digit = self.current_highlight;
+        // 只有候选数模式需要计算候选数
+        let checking = self.highlight_mode == HighlightMode::Candidate && digit.is_some();
+        let values = if checking { self.values() } else { vec![] };
+
+        for &index in cells {
+            let (row, column) = (index / self.size, index % self.size);
+            let cell = &self.numbers[index];
+            let mut flags = CellStates::NONE;
+            if self.selected_cell == Some(index) {
+                flags |= CellStates::SELECTED;
+            }
+
+            match (self.highlight_mode, self.selected_cell, digit) {
+                (HighlightMode::Digit, _, Some(d)) if cell.selected() == Some(d) => {
+                    flags |= CellStates::HIGH_LIGHT;
+                }
+                (HighlightMode::Peers, Some(selected), _) if self.units.is_peer(selected, index) => {
+                    flags |= CellStates::HIGH_LIGHT;
+                }
+                (HighlightMode::Candidate, _, Some(d)) if cell.selected().is_none()
+                    && (self.candidates_with(index, &values) | cell.notes()) & (1 << d) != 0 => {
+                    flags |= CellStates::CHECKING;
+                }
+                _ => {}
+            }
+
+            let states = cell.states();
+            if states & mask != flags {
+                self.numbers[index].set_states((states & !mask) | flags);
+                self.emit_update_cell(row, column);
+            }
+        }
+    }
+
+    pub fn set_update_callback(&mut self, cb: extern "C" fn(*mut c_void, u32, u32)) {
         self.update_callback = Some(cb);
     }
 
     pub fn set_callback_ptr(&mut self, ptr: *mut c_void) {
-        self.callback_ptr = ptr;
+        self.callback_ptr = CallbackPtr(ptr);
     }
 
-    fn emit_update_effect_cell(&self, row: usize, column: usize) {
-        if let Some(cb) = self.update_callback {
-            for idx in self.effect_cell_indexes(row, column).iter() {
-                cb(self.callback_ptr, *idx as u32 / 9, *idx as u32 % 9);
-            }
+    fn emit_update_effect_cell(&mut self, row: usize, column: usize) {
+        if self.update_callback.is_none() {
+            return;
+        }
+
+        let units = self.units.clone();
+        for idx in units.peers(row * self.size + column).iter() {
+            self.emit_update_cell(*idx / self.size, *idx % self.size);
         }
     }
 
-    fn emit_update_cell(&self, row: usize, column: usize) {
-        if let Some(cb) = self.update_callback {
-            cb(self.callback_ptr, row as u32, column as u32);
+    fn emit_update_cell(&mut self, row: usize, column: usize) {
+        let cb = match self.update_callback {
+            Some(cb) => cb,
+            None => return,
+        };
+
+        match self.pending_updates.as_mut() {
+            Some(pending) => pending.push(row * self.size + column),
+            None => cb(self.callback_ptr.0, row as u32, column as u32),
         }
     }
 
-    fn emit_update_all(&self) {
-        if let Some(cb) = self.update_callback {
-            for i in 0..9 {
-                for j in 0..9 {
-                    cb(self.callback_ptr, i as u32, j as u32);
-                }
+    fn emit_update_all(&mut self) {
+        for i in 0..self.size {
+            for j in 0..self.size {
+                self.emit_update_cell(i, j);
             }
         }
     }
 
-    pub fn generate(&mut self) {
+    // 批量操作期间暂存更新, 结束时每个格子只通知一次
+    fn begin_batch(&mut self) {
+        self.pending_updates.get_or_insert_with(Vec::new);
+    }
+
+    fn end_batch(&mut self) {
+        if let Some(mut pending) = self.pending_updates.take() {
+            pending.sort_unstable();
+            pending.dedup();
+            for idx in pending {
+                self.emit_update_cell(idx / self.size, idx % self.size);
+            }
+        }
+    }
+
+    // 生成失败时返回 false, 盘面保持不变
+    pub fn generate(&mut self) -> bool {
+        self.generate_with_progress(&Progress::new())
+    }
+
+    // 生成过程中更新进度, 取消或失败时返回 false, 盘面保持不变
+    pub fn generate_with_progress(&mut self, progress: &Progress) -> bool {
+        self.generate_into(Removal::Difficulty, progress)
+    }
+
+    // 生成极小题目: 去掉任意一个提示数都会多解. 返回提示数的个数.
+    // 只支持不超过 9x9 的盘面, 杀手数独没有提示数, 都返回 None
+    pub fn generate_minimal(&mut self) -> Option<usize> {
+        self.generate_minimal_with_progress(&Progress::new())
+    }
+
+    // 取消时返回 None, 盘面保持不变
+    pub fn generate_minimal_with_progress(&mut self, progress: &Progress) -> Option<usize> {
+        if self.variant == Variant::Killer || self.size > 9 {
+            return None;
+        }
+
+        self.generate_into(Removal::Minimal, progress).then(|| self.clue_count())
+    }
+
+    // 在新盘面上生成, 成功后才替换当前题目
+    fn generate_into(&mut self, removal: Removal, progress: &Progress) -> bool {
+        let mut board = self.new_like();
+        if !board.generate_puzzle(removal, progress) {
+            return false;
+        }
+
+        self.swap_puzzle(board);
+        true
+    }
+
+    fn generate_puzzle(&mut self, removal: Removal, progress: &Progress) -> bool {
+        if self.variant == Variant::Killer {
+            self.cages.clear();
+            self.cage_of = vec![None; self.cell_count()];
+        }
+
         // step 1. generate correct result
-        self.initialize();
-        //self.try_resolve();
-        while !self.try_resolve() {
-            self.initialize();
-            println!("Can't resolve, generate new board: \n{}", *self);
+        // 不规则区域下随机填数很难有解, 直接使用求解器的结果
+        let solution = if self.variant == Variant::Jigsaw { self.random_regions() } else { self.random_solution() };
+        match solution {
+            Some(solution) => self.fill(&solution),
+            // 与取消一样, 盘面处于未完成状态, 由调用者丢弃
+            None => return false,
         }
-        println!("Initialized:\n{}", *self);
+        trace!("Initialized:\n{}", *self);
+        progress.set(0.3);
 
         // step 2. randomize
         let mut rng = rand::thread_rng();
         let pass_count = rng.gen_range(18, 36);
         self.randomize(pass_count);
-        println!("Randomized:\n{}", *self);
+        trace!("Randomized:\n{}", *self);
+        let solution = self.values();
+        progress.set(0.4);
 
         // step 3. remove some block & ensure can be resolve
         // let backup = self.numbers.clone();
-        self.random_remove(50);
-        println!("Blocks Removed:\n{}", *self);
+        let finished = match removal {
+            _ if self.variant == Variant::Killer => self.random_cages(progress),
+            Removal::Difficulty => self.random_remove(self.difficulty.removed_cells(self.cell_count()), progress),
+            Removal::Minimal => self.random_remove(self.cell_count(), progress) && self.remove_redundant(progress),
+            Removal::Clues(target) => self.remove_to_clues(target, &solution, progress),
+        };
+        if !finished {
+            return false;
+        }
+        debug!("Blocks Removed:\n{}", *self);
 
         // step 4. fill candidate & cleanup
         self.reset_init_state();
 
         // step 5. emit update all
         self.emit_update_all();
+        self.start_game(solution);
+        progress.set(1.0);
+
+        true
     }
 
-    fn random_remove(&mut self, count: u32) {
-        let mut removed = 0;
+    // 生成需要特定技巧的题目, 超时后放弃并保留原来的盘面
+    pub fn generate_training(&mut self, goal: TechniqueGoal, timeout: Duration) -> Result<(), GenerateError> {
+        if !goal.is_valid() {
+            return Err(GenerateError::InvalidGoal);
+        }
+
+        // 生成中途到时间也会放弃
+        let progress = Progress::with_deadline(Instant::now() + timeout);
+        while !progress.is_cancelled() {
+            // 在新盘面上生成, 不触发当前盘面的回调
+            let mut candidate = self.new_like();
+            candidate.difficulty = Difficulty::Expert;
+            if !candidate.generate_with_progress(&progress) {
+                break;
+            }
+
+            if goal.accepts(candidate.candidate_grid()) {
+                debug!("Training puzzle for {}:\n{}", goal.required.name(), candidate);
+                let difficulty = self.difficulty;
+                self.swap_puzzle(candidate);
+                self.difficulty = difficulty;
+                return Ok(());
+            }
+        }
+
+        Err(GenerateError::Timeout)
+    }
+
+    // 生成提示数在 [min_clues, max_clues] 内的题目, 超时后放弃并保留原来的盘面.
+    // 每次在范围内随机选一个目标挖空, 挖不到目标时换一个终盘重试. 杀手数独没有提示数
+    pub fn generate_with_clues(&mut self, min_clues: usize, max_clues: usize, timeout: Duration) -> Result<(), GenerateError> {
+        let killer = self.variant == Variant::Killer;
+        if min_clues > max_clues || max_clues > self.cell_count() || (killer && min_clues > 0) {
+            return Err(GenerateError::InvalidClueRange);
+        }
+
         let mut rng = rand::thread_rng();
-        while removed != count {
-            let pick = rng.gen_range(0, 81);
-            if self.numbers[pick].selected().is_some() {
+        // 生成中途到时间也会放弃
+        let progress = Progress::with_deadline(Instant::now() + timeout);
+        while !progress.is_cancelled() {
+            let target = rng.gen_range(min_clues, max_clues + 1);
+            let mut candidate = self.new_like();
+            if !candidate.generate_puzzle(Removal::Clues(target), &progress) {
+                break;
+            }
+
+            let clues = candidate.clue_count();
+            if clues <= max_clues {
+                debug!("Puzzle with {} clues:\n{}", clues, candidate);
+                self.swap_puzzle(candidate);
+                return Ok(());
+            }
+        }
+
+        Err(GenerateError::Timeout)
+    }
+
+    // 相同尺寸、变体和难度的空盘面, 没有回调, 可以交给其他线程生成题目
+    pub fn new_like(&self) -> Self {
+        let mut board = Self::with_shape(self.box_rows, self.box_columns, self.variant).unwrap();
+        board.difficulty = self.difficulty;
+        board.score_config = self.score_config;
+
+        board
+    }
+
+    // 使用 other 的题目开始新的一局, 保留回调、计时源和计分设置
+    pub fn swap_puzzle(&mut self, other: Board) {
+        self.size = other.size;
+        self.box_rows = other.box_rows;
+        self.box_columns = other.box_columns;
+        self.variant = other.variant;
+        self.difficulty = other.difficulty;
+        self.numbers = other.numbers;
+        self.units = other.units;
+        self.regions = other.regions;
+        self.cages = other.cages;
+        self.cage_of = other.cage_of;
+        self.combinations = other.combinations;
+        self.current_highlight = None;
+        self.highlight_mode = HighlightMode::None;
+        self.selected_cell = None;
+        self.emit_update_all();
+        self.start_game(other.solution);
+        self.seed = other.seed;
+    }
+
+    // 挖到只剩 target 个提示数, 挖不到时在同一个终盘上换挖空顺序重试几次. 取消时返回 false
+    fn remove_to_clues(&mut self, target: usize, solution: &[u8], progress: &Progress) -> bool {
+        for attempt in 0..REMOVAL_ATTEMPTS {
+            if attempt > 0 {
+                trace!("Removal attempt {} stopped above {} clues", attempt, target);
+                self.fill(solution);
+            }
+            if !self.random_remove(self.cell_count() - target, progress) {
+                return false;
+            }
+            if self.values().iter().filter(|&&v| v != 0).count() <= target {
+                break;
+            }
+        }
+
+        true
+    }
+
+    // 随机挖空, 跳过会导致多解的格子. 取消时返回 false
+    fn random_remove(&mut self, count: usize, progress: &Progress) -> bool {
+        let mut cells: Vec<usize> = (0..self.cell_count()).collect();
+        cells.shuffle(&mut rand::thread_rng());
+
+        let mut values = self.values();
+        let mut removed = 0;
+        for pick in cells {
+            if removed == count {
+                break;
+            }
+            if progress.is_cancelled() {
+                return false;
+            }
+            progress.set(0.4 + 0.6 * removed as f32 / count as f32);
+
+            if values[pick] == 0 {
+                continue;
+            }
+
+            let backup = values[pick];
+            values[pick] = 0;
+            if self.is_unique(&values, progress) {
                 self.numbers[pick].set_select(None);
                 removed += 1;
+            } else {
+                values[pick] = backup;
+            }
+        }
+
+        true
+    }
+
+    // random_remove 中搜索超时的格子会被保留, 用完整搜索再检查一遍剩下的提示数
+    fn remove_redundant(&mut self, progress: &Progress) -> bool {
+        let mut values = self.values();
+        let mut cells: Vec<usize> = (0..self.cell_count()).filter(|&i| values[i] != 0).collect();
+        cells.shuffle(&mut rand::thread_rng());
+
+        for pick in cells {
+            if progress.is_cancelled() {
+                return false;
+            }
+
+            let backup = values[pick];
+            values[pick] = 0;
+            if self.is_unique_exact(&values, progress) {
+                self.numbers[pick].set_select(None);
+            } else {
+                values[pick] = backup;
             }
         }
+
+        true
+    }
+
+    fn is_unique_exact(&self, values: &[u8], progress: &Progress) -> bool {
+        self.count_exact(values, progress) == Some(1)
+    }
+
+    // 最多数到 2 个解, 超过 EXACT_NODE_LIMIT 或取消时返回 None
+    fn count_exact(&self, values: &[u8], progress: &Progress) -> Option<usize> {
+        let mut solver = match Solver::with_cages(&self.units, &self.cages, values) {
+            Some(solver) => solver.node_limit(EXACT_NODE_LIMIT).cancel_on(progress),
+            None => return Some(0),
+        };
+        let count = solver.count_solutions(2);
+
+        (!solver.aborted()).then_some(count)
+    }
+
+    // 题目给出的数字个数
+    pub fn clue_count(&self) -> usize {
+        self.givens().iter().filter(|&&v| v != 0).count()
+    }
+
+    // 题目有唯一解, 且去掉任意一个提示数都会多解. 杀手数独没有提示数,
+    // 搜索超过节点上限无法确认时也返回 false
+    pub fn is_minimal(&self) -> bool {
+        if self.variant == Variant::Killer {
+            return false;
+        }
+
+        let progress = Progress::new();
+        let mut givens = self.givens();
+        if !self.is_unique_exact(&givens, &progress) {
+            return false;
+        }
+
+        let clues: Vec<usize> = (0..givens.len()).filter(|&i| givens[i] != 0).collect();
+        clues.into_iter().all(|i| {
+            let backup = givens[i];
+            givens[i] = 0;
+            let count = self.count_exact(&givens, &progress);
+            givens[i] = backup;
+            count == Some(2)
+        })
+    }
+
+    // 搜索超时或取消按多解处理
+    fn is_unique(&self, values: &[u8], progress: &Progress) -> bool {
+        self.unique_solution(values, progress).is_some()
+    }
+
+    fn unique_solution(&self, values: &[u8], progress: &Progress) -> Option<Vec<u8>> {
+        let mut solver = Solver::with_cages(&self.units, &self.cages, values)?
+            .node_limit(self.cell_count() * 50)
+            .cancel_on(progress);
+        (solver.count_solutions(2) == 1 && !solver.aborted()).then(|| solver.solutions()[0].clone())
     }
 
     // 在保持解不变的情况化进行随机化处理
     fn randomize(&mut self, pass_count: usize) {
+        match self.variant {
+            Variant::Standard | Variant::Killer => self.randomize_standard(pass_count),
+            Variant::Diagonal => self.randomize_diagonal(pass_count),
+            // 行列交换会破坏区域, 只做数字替换
+            Variant::Jigsaw => self.relabel_digits(),
+        }
+    }
+
+    fn relabel_digits(&mut self) {
+        let mut digits: Vec<u8> = (1..=self.size as u8).collect();
+        digits.shuffle(&mut rand::thread_rng());
+
+        for cell in self.numbers.iter_mut() {
+            if let Some(v) = cell.selected() {
+                cell.set_select(Some(digits[v as usize - 1]));
+            }
+        }
+    }
+
+    fn randomize_standard(&mut self, pass_count: usize) {
+        let size = self.size;
         let mut rng = rand::thread_rng();
         for _ in 0..pass_count {
             let row_or_column = rng.gen_bool(0.5);
-            let block = rng.gen_range(0, 3);
-            let pick1 = rng.gen_range(0, 3);
-            let pick2 = rng.gen_range(0, 3);
+            // 行在同一横向宫组内交换, 列在同一纵向宫组内交换
+            let (band_size, bands) = if row_or_column {
+                (self.box_rows, size / self.box_rows)
+            } else {
+                (self.box_columns, size / self.box_columns)
+            };
+            let block = rng.gen_range(0, bands);
+            let pick1 = rng.gen_range(0, band_size);
+            let pick2 = rng.gen_range(0, band_size);
             if pick1 == pick2 {
                 continue;
             }
 
             if row_or_column {
                 // switch row
-                let row1 = block * 3 + pick1;
-                let row2 = block * 3 + pick2;
-                for i in 0..9 {
-                    self.numbers.swap(row1 * 9 + i, row2 * 9 + i);
+                let row1 = block * band_size + pick1;
+                let row2 = block * band_size + pick2;
+                for i in 0..size {
+                    self.numbers.swap(row1 * size + i, row2 * size + i);
                 }
             } else {
                 // switch column
-                let column1 = block * 3 + pick1;
-                let column2 = block * 3 + pick2;
-                for i in 0..9 {
-                    self.numbers.swap(i * 9 + column1, i * 9 + column2);
+                let column1 = block * band_size + pick1;
+                let column2 = block * band_size + pick2;
+                for i in 0..size {
+                    self.numbers.swap(i * size + column1, i * size + column2);
+                }
+            }
+        }
+    }
+
+    // 行和列同时做关于中心对称的置换, 两条对角线保持不变
+    fn randomize_diagonal(&mut self, pass_count: usize) {
+        // 宫不是正方形时行列无法同时置换
+        if self.box_rows != self.box_columns {
+            self.relabel_digits();
+            return;
+        }
+
+        let size = self.size;
+        let band_size = self.box_rows;
+        let bands = size / band_size;
+        let mut rng = rand::thread_rng();
+        for _ in 0..pass_count {
+            let mut perm: Vec<usize> = (0..size).collect();
+            let band = rng.gen_range(0, bands);
+            if rng.gen_bool(0.5) {
+                // switch band with its mirror band
+                let mirror = bands - 1 - band;
+                for i in 0..band_size {
+                    perm[band * band_size + i] = mirror * band_size + i;
+                    perm[mirror * band_size + i] = band * band_size + i;
+                }
+            } else {
+                let a = band * band_size + rng.gen_range(0, band_size);
+                let b = band * band_size + rng.gen_range(0, band_size);
+                let (ma, mb) = (size - 1 - a, size - 1 - b);
+                if a == b {
+                    continue;
+                }
+
+                if (ma, mb) == (b, a) {
+                    perm.swap(a, b);
+                } else if ma != a && ma != b && mb != a && mb != b {
+                    perm.swap(a, b);
+                    perm.swap(ma, mb);
+                } else {
+                    continue;
+                }
+            }
+
+            let old = self.numbers.clone();
+            for r in 0..size {
+                for c in 0..size {
+                    self.numbers[perm[r] * size + perm[c]] = old[r * size + c].clone();
+                }
+            }
+        }
+    }
+
+    fn reset_init_state(&mut self) {
+        // collect all selected
+        let selected: Vec<(usize, u8)> = self.numbers.iter().enumerate()
+            .filter(|(_, cell)| cell.selected().is_some())
+            .map(|(idx, cell)| (idx, cell.selected().unwrap()))
+            .collect();
+
+        // re-generate board
+        self.numbers = self.blank_cells();
+
+        // write new data
+        let size = self.size;
+        for (idx, select) in selected {
+            let cell = self.cell_mut(idx / size, idx % size);
+            cell.set_states(cell.states() | CellStates::PRE_FILLED);
+
+            self.set(idx / size, idx % size, Some(select));
+        }
+    }
+
+    // 已填数字, 空格为 0
+    pub fn values(&self) -> Vec<u8> {
+        self.numbers.iter().map(|x| x.selected().unwrap_or(0)).collect()
+    }
+
+    // 每格一个字符, '.' 或 '0' 表示空格, 10 以上用字母
+    fn parse(&self, puzzle: &str) -> Option<Vec<u8>> {
+        let mut values = vec![];
+        for c in puzzle.chars().filter(|c| !c.is_whitespace()) {
+            let val = match c {
+                '.' | '0' => 0,
+                c => match char_to_digit(c) {
+                    Some(v) if v as usize <= self.size => v,
+                    _ => return None,
+                },
+            };
+            values.push(val);
+        }
+
+        (values.len() == self.cell_count()).then_some(values)
+    }
+
+    // 按行读入题目并开始计时
+    pub fn load(&mut self, puzzle: &str) -> bool {
+        let values = match self.parse(puzzle) {
+            Some(values) => values,
+            None => return false,
+        };
+
+        for (cell, val) in self.numbers.iter_mut().zip(values) {
+            cell.set_select(if val == 0 { None } else { Some(val) });
+        }
+        self.reset_init_state();
+        self.emit_update_all();
+
+        // 任意输入都可能很难搜索, 超时按没有唯一解处理
+        let solution = self.unique_solution(&self.values(), &Progress::new());
+        self.start_game(solution.unwrap_or_default());
+
+        true
+    }
+
+    // 保存题目、当前进度和用时
+    pub fn save(&self) -> String {
+        let givens = self.givens().into_iter()
+            .map(|v| if v == 0 { '.' } else { digit_to_char(v) })
+            .collect();
+
+        let data = SaveData {
+            version: SaveData::VERSION,
+            box_rows: self.box_rows,
+            box_columns: self.box_columns,
+            variant: self.variant,
+            difficulty: self.difficulty,
+            seed: self.seed,
+            givens,
+            values: self.export(),
+            regions: if self.variant == Variant::Jigsaw { self.regions.clone() } else { vec![] },
+            cages: self.cages.clone(),
+            notes: self.numbers.iter().map(|x| x.notes()).collect(),
+            excluded: self.numbers.iter().map(|x| x.excluded()).collect(),
+            stats: self.stats,
+            elapsed_ms: self.elapsed_ms(),
+            paused: self.is_paused(),
+        };
+
+        serde_json::to_string(&data).unwrap()
+    }
+
+    // 从 save 的结果恢复, 数据无效时返回 None
+    pub fn from_save(data: &str) -> Option<Self> {
+        let data: SaveData = serde_json::from_str(data).ok()?;
+        if data.version != SaveData::VERSION {
+            return None;
+        }
+
+        let mut board = Self::with_shape(data.box_rows, data.box_columns, data.variant)?;
+        board.difficulty = data.difficulty;
+        if data.variant == Variant::Jigsaw && !board.set_regions(data.regions) {
+            return None;
+        }
+        if data.variant == Variant::Killer && !board.set_cages(data.cages) {
+            return None;
+        }
+
+        let values = board.parse(&data.values)?;
+        if !board.load(&data.givens) {
+            return None;
+        }
+
+        let size = board.size;
+        for (idx, val) in values.into_iter().enumerate() {
+            let cell = &board.numbers[idx];
+            if cell.is_prefilled() {
+                if cell.selected() != Some(val) {
+                    return None;
                 }
+            } else if val != 0 {
+                board.set(idx / size, idx % size, Some(val));
+            }
+        }
+        if !data.notes.is_empty() {
+            if data.notes.len() != board.cell_count() {
+                return None;
+            }
+            for (cell, notes) in board.numbers.iter_mut().zip(data.notes) {
+                cell.set_notes(notes & board_digits(size));
+            }
+        }
+        if !data.excluded.is_empty() {
+            if data.excluded.len() != board.cell_count() {
+                return None;
+            }
+            for (cell, excluded) in board.numbers.iter_mut().zip(data.excluded) {
+                cell.set_excluded(excluded & board_digits(size));
             }
         }
+        board.stats = data.stats;
+        board.seed = data.seed;
+        board.clock.restore(data.elapsed_ms, data.paused);
+
+        Some(board)
+    }
+
+    // 题目给出的数字, 其他格子为 0
+    pub fn givens(&self) -> Vec<u8> {
+        self.numbers.iter()
+            .map(|x| if x.is_prefilled() { x.selected().unwrap_or(0) } else { 0 })
+            .collect()
     }
 
-    pub fn initialize(&mut self) {
-        // reset
-        for x in &mut self.numbers {
-            x.reset_candidate();
-            x.set_select(None);
-            x.set_states(CellStates::NONE);
+    // 题目在等价变换下的规范形式, 只支持不超过 9x9 的标准数独
+    pub fn canonical(&self) -> Option<String> {
+        if self.variant != Variant::Standard {
+            return None;
         }
 
-        let mut rng = rand::thread_rng();
-        let mut generated = 0;
-        while generated != 11 {
-            let pos = rng.gen_range(0, 81);
-            let cell = self.cell(pos / 9, pos % 9).clone();
-            if cell.selected().is_some() {
-                continue;
-            }
+        let values = canonical::canonical_form(&self.givens(), self.box_rows, self.box_columns)?;
+        Some(values.into_iter().map(|v| if v == 0 { '.' } else { digit_to_char(v) }).collect())
+    }
 
-            let candidate = rng.gen_range(1, 10);
-            if cell.has_candidate(candidate) {
-                self.set(pos / 9, pos % 9, Some(candidate));
-                self.cell_mut(pos / 9, pos % 9).set_states(cell.states() | CellStates::PRE_FILLED);
-                generated += 1;
-            }
+    // 对题目、答案、已填数字、笔记和撤销记录做同一变换, 变体不允许时返回 false.
+    // 对角线变体只能用保持对角线的变换, 不规则区域和笼子只能旋转翻转, 笼子还不能替换数字
+    pub fn transform(&mut self, t: &Transform) -> bool {
+        let allowed = t.box_shape() == self.box_shape() && match self.variant {
+            Variant::Standard => true,
+            Variant::Diagonal => t.keeps_diagonals(),
+            Variant::Jigsaw => t.is_geometric(),
+            Variant::Killer => t.is_geometric() && t.keeps_digits(),
+        };
+        if !allowed {
+            return false;
         }
-    }
 
-    fn reset_init_state(&mut self) {
-        // collect all selected
-        let selected: Vec<(usize, u8)> = self.numbers.iter().enumerate()
-            .filter(|(_, cell)| cell.selected().is_some())
-            .map(|(idx, cell)| (idx, cell.selected().unwrap()))
-            .collect();
+        let givens = t.apply(&self.givens());
+        let values = t.apply(&self.values());
+        let notes = t.apply_masks(&self.numbers.iter().map(|x| x.notes()).collect::<Vec<_>>());
+        let excluded = t.apply_masks(&self.numbers.iter().map(|x| x.excluded()).collect::<Vec<_>>());
 
-        // re-generate board
-        self.numbers = (0..81).map(|_| Cell::new()).collect();
+        if self.variant == Variant::Jigsaw {
+            self.regions = t.move_cells(&self.regions);
+            self.units = Arc::new(Units::from_regions(self.size, &self.regions));
+        }
+        if self.variant == Variant::Killer {
+            self.cages = self.cages.iter()
+                .map(|c| Cage::new(c.cells().iter().map(|&i| t.target(i)).collect(), c.sum()))
+                .collect();
+            self.cage_of = cage::cage_map(&self.cages, self.cell_count());
+        }
 
-        // write new data
-        for (idx, select) in selected {
-            let cell = self.cell_mut(idx / 9, idx % 9);
-            cell.set_states(cell.states() | CellStates::PRE_FILLED);
+        // 与 load 相同的方式重建题目, 再填入玩家的数字
+        self.numbers = self.blank_cells();
+        for (cell, &val) in self.numbers.iter_mut().zip(givens.iter()) {
+            cell.set_select(Some(val).filter(|&v| v != 0));
+        }
+        self.reset_init_state();
+        let size = self.size;
+        for (idx, val) in values.into_iter().enumerate() {
+            if val != 0 && givens[idx] == 0 {
+                self.set(idx / size, idx % size, Some(val));
+            }
+        }
+        for (idx, cell) in self.numbers.iter_mut().enumerate() {
+            cell.set_notes(notes[idx]);
+            cell.set_excluded(excluded[idx]);
+        }
 
-            self.set(idx / 9, idx % 9, Some(select));
+        if !self.solution.is_empty() {
+            self.solution = t.apply(&self.solution);
         }
-    }
+        self.history = self.history.drain(..).map(|a| transform_action(a, t)).collect();
+        self.selected_cell = self.selected_cell.map(|i| t.target(i));
+        self.current_highlight = self.current_highlight.map(|d| t.digit(d));
 
-    fn try_resolve(&mut self) -> bool {
-        let filled: Vec<isize> = self.numbers.iter()
-            .enumerate().filter(|(_, cell)| {
-            cell.selected().is_some()
-        }).map(|(idx, _)| idx as isize).collect();
+        self.emit_update_all();
+        self.refresh_highlight();
 
-        let mut current: isize = 0;
-        let mut rollback = false;
-        let mut try_times = 0;
-        'fill: while current != 81 && current >= 0 {
-            if filled.contains(&current) {
-                if rollback {
-                    current -= 1;
-                } else {
-                    current += 1;
-                }
-                continue 'fill;
-            }
+        true
+    }
 
-            if try_times > 10000 { return false; }
-            try_times += 1;
+    // 两道题目是否可以通过等价变换互相得到
+    pub fn is_equivalent(&self, other: &Board) -> bool {
+        self.box_shape() == other.box_shape() && self.canonical().is_some_and(|c| other.canonical() == Some(c))
+    }
 
-            rollback = false;
-            let cell = &self.numbers[current as usize];
-            let available_candidates = cell.best_candidates();
+    pub fn export(&self) -> String {
+        self.numbers.iter()
+            .map(|x| x.selected().map_or('.', digit_to_char))
+            .collect()
+    }
 
-            //println!("try to fill cell {} with candidates {:?}", current, available_candidates);
-            for try_num in available_candidates.iter() {
-                //println!("write {} to cell {}", try_num, current);
-                if !self.set(current as usize / 9, current as usize % 9, Some(*try_num)) {
-                    current += 1;
-                    continue 'fill;
-                }
-            }
+    pub fn solve(&self) -> Option<Vec<u8>> {
+        Solver::with_cages(&self.units, &self.cages, &self.values()).and_then(|mut s| s.solve())
+    }
 
-            //println!("rollback cell {}", current);
-            // all failed, rollback
-            self.set(current as usize / 9, current as usize % 9, None);
-            rollback = true;
-            current -= 1;
-        }
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        Solver::with_cages(&self.units, &self.cages, &self.values()).map_or(0, |mut s| s.count_solutions(limit))
+    }
 
-        current == 81
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
     }
 
     pub fn check(&self, row: usize, column: usize) -> bool {
@@ -261,27 +1297,21 @@ impl Board {
     }
 
     pub fn set(&mut self, row: usize, column: usize, val: Option<u8>) -> bool {
+        debug_assert!(val.is_none_or(|v| v >= 1 && v as usize <= self.size));
+        let index = row * self.size + column;
+
         // add candidate back if already has value
         if let Some(v) = self.cell(row, column).selected() {
             self.cell_mut(row, column).add_candidate(v);
 
             // add effect candidates
-            for idx in self.effect_cell_indexes(row, column).iter() {
-                self.numbers[*idx].add_candidate(v);
+            for &idx in self.units.peers(index) {
+                self.numbers[idx].add_candidate(v);
             }
         }
 
         // current cell updates
-        let highlight = val.is_some() && self.current_highlight == val;
-        let cell = self.cell_mut(row, column);
-        cell.set_select(val);
-
-        // update highlight status
-        if highlight {
-            cell.set_states(cell.states() | CellStates::HIGH_LIGHT);
-        } else {
-            cell.set_states(cell.states() & !CellStates::HIGH_LIGHT);
-        }
+        self.cell_mut(row, column).set_select(val);
 
         // set new value
         let mut error_occured = false;
@@ -289,15 +1319,13 @@ impl Board {
             let cell = self.cell_mut(row, column);
             cell.set_states(cell.states() | CellStates::FILLED);
             if cell.remove_candidate(v) {
-                //println!("No candidate on cell {} {}", row, column);
                 error_occured = true;
             }
 
             // remove effect candidates
-            for idx in self.effect_cell_indexes(row, column).iter() {
-                if self.numbers[*idx].remove_candidate(v) {
+            for &idx in self.units.peers(index) {
+                if self.numbers[idx].remove_candidate(v) {
                     error_occured = true;
-                    //println!("No candidate on cell {} {}", row, column);
                 }
             }
         } else {
@@ -305,78 +1333,271 @@ impl Board {
             cell.set_states(cell.states() & !CellStates::FILLED);
         }
 
+        // cage sum may be over/under now
+        if let Some(cage) = self.cage_of[index] {
+            self.update_cage_conflict(cage);
+            for idx in self.cages[cage].cells().to_vec() {
+                self.emit_update_cell(idx / self.size, idx % self.size);
+            }
+        }
+
         // emit updates
         self.emit_update_effect_cell(row, column);
         self.emit_update_cell(row, column);
 
+        // 同一单元和笼子中的候选数可能变化
+        let mut affected = vec![index];
+        affected.extend_from_slice(self.units.peers(index));
+        if let Some(cage) = self.cage_of[index] {
+            affected.extend_from_slice(self.cages[cage].cells());
+        }
+        self.refresh_highlight_cells(&affected);
+
         error_occured
     }
 
-    fn effect_cell_indexes(&self, row: usize, column: usize) -> [usize; 20] {
-        let mut indexes = [0; 20];
-        let mut index = 0;
+    // 玩家填数或清除, 不能修改题目给出的格子. 返回本次输入是否算作错误,
+    // 数字不在 1..=size 内时不做任何操作
+    pub fn enter(&mut self, row: usize, column: usize, val: Option<u8>) -> bool {
+        if val.is_some_and(|v| v == 0 || v as usize > self.size) {
+            return false;
+        }
+
+        let index = row * self.size + column;
+        let cell = self.cell(row, column);
+        if cell.is_prefilled() || cell.selected() == val {
+            return false;
+        }
+
+        self.history.push(Action::Value { index, old: cell.selected() });
+        self.set(row, column, val);
+
+        let mistake = val.is_some() && self.is_wrong(index);
+        if mistake {
+            self.stats.mistakes += 1;
+        }
+
+        // 完成后停止计时
+        if self.is_solved() {
+            self.clock.pause();
+        }
+
+        mistake
+    }
+
+    // 与答案不符, 或与其它格子冲突
+    fn is_wrong(&self, index: usize) -> bool {
+        let val = self.numbers[index].selected();
+        if val.is_none() {
+            return false;
+        }
+
+        if let Some(solution) = self.solution() {
+            return val != Some(solution[index]);
+        }
+
+        !self.check(index / self.size, index % self.size)
+            || self.cage_of[index].is_some_and(|c| self.cages[c].is_broken(&self.values()))
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.numbers.iter().all(|x| x.selected().is_some())
+            && (0..self.cell_count()).all(|i| !self.is_wrong(i))
+    }
+
+    // 切换格子上的笔记, 已填数字的格子不能记笔记
+    pub fn toggle_note(&mut self, row: usize, column: usize, val: u8) -> bool {
+        let index = row * self.size + column;
+        let cell = self.cell(row, column);
+        if cell.selected().is_some() || val == 0 || val as usize > self.size {
+            return false;
+        }
+
+        let old = cell.notes();
+        self.history.push(Action::Notes { index, old });
+        self.cell_mut(row, column).set_notes(old ^ (1 << val));
+        if old & (1 << val) == 0 {
+            self.stats.notes += 1;
+        }
+        self.emit_update_cell(row, column);
+        self.refresh_highlight_cells(&[index]);
+
+        true
+    }
+
+    // 填入一个答案: 优先修正填错的格子, 其次是第一个空格
+    pub fn hint(&mut self) -> Option<(usize, usize)> {
+        let solution = self.solution()?;
+        let values = self.values();
+        let index = (0..values.len()).find(|&i| values[i] != 0 && values[i] != solution[i])
+            .or_else(|| values.iter().position(|&v| v == 0))?;
+        let val = solution[index];
+
+        self.history.push(Action::Value { index, old: self.numbers[index].selected() });
+        self.set(index / self.size, index % self.size, Some(val));
+        self.stats.hints += 1;
+        if self.is_solved() {
+            self.clock.pause();
+        }
+
+        Some((index / self.size, index % self.size))
+    }
+
+    // 撤销上一次 enter/toggle_note/hint
+    pub fn undo(&mut self) -> bool {
+        let action = match self.history.pop() {
+            Some(action) => action,
+            None => return false,
+        };
+
+        self.begin_batch();
+        self.revert(action);
+        self.end_batch();
+        self.stats.undos += 1;
+
+        true
+    }
 
-        // same row
-        for i in 0..=8 {
-            if i != column {
-                indexes[index] = row * 9 + i;
-                index += 1;
+    fn revert(&mut self, action: Action) {
+        match action {
+            Action::Value { index, old } => {
+                self.set(index / self.size, index % self.size, old);
+            }
+            Action::Notes { index, old } => {
+                self.numbers[index].set_notes(old);
+                self.emit_update_cell(index / self.size, index % self.size);
+                self.refresh_highlight_cells(&[index]);
+            }
+            Action::Batch(actions) => {
+                for action in actions.into_iter().rev() {
+                    self.revert(action);
+                }
             }
         }
+    }
 
-        // same column
-        for i in 0..=8 {
-            if i != row {
-                indexes[index] = i * 9 + column;
-                index += 1;
+    // 所有空格的笔记设为当前候选数, 返回改变的格子数
+    pub fn autofill_notes(&mut self) -> usize {
+        let mut actions = vec![];
+        let values = self.values();
+        self.begin_batch();
+        for index in 0..self.cell_count() {
+            let (row, column) = (index / self.size, index % self.size);
+            let old = self.numbers[index].notes();
+            let notes = self.candidates_with(index, &values);
+            if self.numbers[index].selected().is_some() || old == notes {
+                continue;
             }
+
+            actions.push(Action::Notes { index, old });
+            self.numbers[index].set_notes(notes);
+            self.emit_update_cell(row, column);
         }
+        let changed: Vec<usize> = actions.iter().filter_map(|a| match a {
+            Action::Notes { index, .. } => Some(*index),
+            _ => None,
+        }).collect();
+        self.refresh_highlight_cells(&changed);
+        self.end_batch();
+
+        let count = actions.len();
+        if count > 0 {
+            self.history.push(Action::Batch(actions));
+            self.stats.notes += 1;
+        }
+
+        count
+    }
 
-        // same block
-        let br = (row / 3) * 3;
-        let bc = (column / 3) * 3;
-        for r in 0..=2 {
-            for c in 0..=2 {
-                let cr = br + r;
-                let cc = bc + c;
+    // 填入当前所有只剩一个候选数的格子, 返回填入的个数
+    pub fn apply_naked_singles(&mut self) -> usize {
+        let grid = self.candidate_grid();
+        let singles = (0..self.cell_count())
+            .filter(|&c| grid.candidates(c).count_ones() == 1)
+            .map(|c| (c, grid.candidates(c).trailing_zeros() as u8))
+            .collect();
 
+        self.place_all(singles)
+    }
 
-                if cr != row && cc != column {
-                    indexes[index] = cr * 9 + cc;
-                    index += 1;
+    // 填入当前所有在某个单元中只有一个位置的数字, 返回填入的个数
+    pub fn apply_hidden_singles(&mut self) -> usize {
+        let grid = self.candidate_grid();
+        let mut singles = vec![];
+        for unit in grid.units().units() {
+            for digit in 1..=self.size as u8 {
+                let mut cells = unit.cells().iter().filter(|&&c| grid.has(c, digit));
+                if let (Some(&cell), None) = (cells.next(), cells.next()) {
+                    singles.push((cell, digit));
                 }
             }
         }
 
-        debug_assert!(20 == index);
-        indexes
+        self.place_all(singles)
+    }
+
+    // 作为一次可撤销的操作填入, 已经不再是候选数的跳过
+    fn place_all(&mut self, singles: Vec<(usize, u8)>) -> usize {
+        let mut actions = vec![];
+        self.begin_batch();
+        for (index, digit) in singles {
+            let (row, column) = (index / self.size, index % self.size);
+            if self.numbers[index].selected().is_some() || self.candidates(row, column) & (1 << digit) == 0 {
+                continue;
+            }
+
+            actions.push(Action::Value { index, old: None });
+            self.set(row, column, Some(digit));
+        }
+        self.end_batch();
+
+        let count = actions.len();
+        if count > 0 {
+            self.history.push(Action::Batch(actions));
+            if self.is_solved() {
+                self.clock.pause();
+            }
+        }
+
+        count
+    }
+
+    fn effect_cell_indexes(&self, row: usize, column: usize) -> &[usize] {
+        self.units.peers(row * self.size + column)
     }
 
     pub fn cell(&self, row: usize, column: usize) -> &Cell {
-        debug_assert!(row < 9 && column < 9);
+        debug_assert!(row < self.size && column < self.size);
 
-        &self.numbers[row * 9 + column]
+        &self.numbers[row * self.size + column]
     }
 
     pub fn cell_mut(&mut self, row: usize, column: usize) -> &mut Cell {
-        &mut self.numbers[row * 9 + column]
+        &mut self.numbers[row * self.size + column]
     }
 }
 
+// 所有数字对应的位
+fn board_digits(size: usize) -> u32 {
+    ((1 << size) - 1) << 1
+}
+
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for row in 0..9 {
-            if row % 3 == 0 {
-                writeln!(f, "+-----+-----+-----+")?;
+        let stacks = self.size / self.box_columns;
+        let line = format!("+{}", format!("{}+", "-".repeat(self.box_columns * 2 - 1)).repeat(stacks));
+
+        for row in 0..self.size {
+            if row % self.box_rows == 0 {
+                writeln!(f, "{}", line)?;
             }
             write!(f, "|")?;
-            for column in 0..9 {
-                let index = row * 9 + column;
+            for column in 0..self.size {
+                let index = row * self.size + column;
                 let s = self.numbers[index].selected()
-                    .map(|x| x.to_string())
-                    .unwrap_or(" ".to_string());
+                    .map_or(' ', digit_to_char);
                 write!(f, "{}", s)?;
-                if column % 3 == 2 {
+                if column % self.box_columns == self.box_columns - 1 {
                     write!(f, "|")?;
                 } else {
                     write!(f, " ")?;
@@ -384,14 +1605,28 @@ impl fmt::Display for Board {
             }
             writeln!(f)?;
         }
-        writeln!(f, "+-----+-----+-----+")
+        writeln!(f, "{}", line)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::board::Board;
+    use crate::board::{box_shape, Board};
     use crate::board::CellStates;
+    use crate::board::Variant;
+    use crate::cage::Cage;
+    use crate::cell::HighlightMode;
+    use crate::clock::ManualTime;
+    use crate::daily::Date;
+    use crate::difficulty::Difficulty;
+    use crate::job::Progress;
+    use crate::region;
+    use crate::strategy::Technique;
+    use crate::training::{GenerateError, TechniqueGoal};
+    use crate::transform::Transform;
+    use std::time::{Duration, Instant};
+    use std::os::raw::c_void;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[test]
     fn test_remove_candidate()
@@ -429,13 +1664,45 @@ mod tests {
     fn test_randomize()
     {
         let mut board = Board::empty();
-        board.initialize();
-        while !board.try_resolve() {
-            board.initialize();
-        }
+        let solution = board.random_solution().unwrap();
+        board.fill(&solution);
 
         board.randomize(3);
-        assert!(board.try_resolve());
+        assert!(board.has_unique_solution());
+        assert!((0..81).all(|i| board.check(i / 9, i % 9)));
+    }
+
+    // 终盘的每个行、列、宫都恰好包含所有数字
+    fn assert_complete(board: &Board, solution: &[u8]) {
+        assert!(solution.iter().all(|&v| v != 0));
+        for unit in board.units.units() {
+            let digits = unit.cells().iter().fold(0u32, |mask, &i| mask | 1 << solution[i]);
+            assert_eq!(((1 << board.size) - 1) << 1, digits);
+        }
+    }
+
+    #[test]
+    fn test_random_solution() {
+        for variant in [Variant::Standard, Variant::Diagonal] {
+            for size in [4, 6, 9, 12, 16] {
+                let board = Board::with_size(size, variant).unwrap();
+                assert_complete(&board, &board.random_solution().unwrap());
+            }
+        }
+
+        // 大致均匀: 每个格子上各数字出现的次数接近, 终盘互不相同
+        let board = Board::empty();
+        let solutions: Vec<Vec<u8>> = (0..900).map(|_| board.random_solution().unwrap()).collect();
+        for cell in [0, 40, 80] {
+            for digit in 1..=9 {
+                let count = solutions.iter().filter(|s| s[cell] == digit).count();
+                assert!((50..=150).contains(&count), "digit {} appears {} times at {}", digit, count, cell);
+            }
+        }
+        let mut unique = solutions.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(solutions.len(), unique.len());
     }
 
     #[test]
@@ -489,7 +1756,9 @@ mod tests {
     #[test]
     fn test_cell_state() {
         let mut board = Board::empty();
-        board.initialize();
+        let solution = board.random_solution().unwrap();
+        board.fill(&solution[..11]);
+        board.reset_init_state();
 
         let mut pre_filled = 0;
         for i in 0..=80 {
@@ -500,10 +1769,8 @@ mod tests {
         }
         assert_eq!(pre_filled, 11);
 
-        let mut board = Board::empty();
-        board.initialize();
-        while !board.try_resolve() {
-            board.initialize();
+        for (i, &v) in solution.iter().enumerate().skip(11) {
+            board.set(i / 9, i % 9, Some(v));
         }
 
         let mut pre_filled = 0;
@@ -521,6 +1788,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_highlight_set() {
         let mut board = Board::empty();
         assert_eq!(false, (board.cell(0, 0).states() & CellStates::HIGH_LIGHT) == CellStates::HIGH_LIGHT);
@@ -542,4 +1810,666 @@ mod tests {
         board.set_current_highlight(None);
         assert_eq!(false, (board.cell(0, 0).states() & CellStates::HIGH_LIGHT) == CellStates::HIGH_LIGHT);
     }
+
+    #[test]
+    fn test_selection_highlight() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        let mut updates = 0u32;
+        board.set_update_callback(count_update_ptr);
+        board.set_callback_ptr(&mut updates as *mut u32 as *mut c_void);
+        let count = |board: &Board, state: CellStates| {
+            (0..81).filter(|&i| board.numbers[i].states().contains(state)).count()
+        };
+
+        board.select(Some((4, 4)));
+        assert_eq!(Some((4, 4)), board.selected_cell());
+        assert_eq!(1, count(&board, CellStates::SELECTED));
+        assert_eq!(1, updates);
+
+        board.set_highlight(HighlightMode::Peers, None);
+        assert_eq!(HighlightMode::Peers, board.highlight_mode());
+        assert_eq!(20, count(&board, CellStates::HIGH_LIGHT));
+        assert!(!board.cell(4, 4).is_highlighted());
+        assert_eq!(21, updates);
+
+        // 移动选中格子时只通知变化的格子
+        updates = 0;
+        board.select(Some((4, 5)));
+        assert_eq!(20, count(&board, CellStates::HIGH_LIGHT));
+        assert!(board.cell(4, 4).is_highlighted());
+        assert_eq!(2 + 6 + 6, updates);
+
+        board.set_highlight(HighlightMode::Candidate, Some(4));
+        assert_eq!(0, count(&board, CellStates::HIGH_LIGHT));
+        let expected = (0..81).filter(|&i| board.candidates(i / 9, i % 9) & (1 << 4) != 0).count();
+        assert_eq!(expected, count(&board, CellStates::CHECKING));
+
+        // 填数后候选数变化, 高亮随之更新
+        let (row, column) = (0..81).map(|i| (i / 9, i % 9))
+            .find(|&(r, c)| board.cell(r, c).states().contains(CellStates::CHECKING)).unwrap();
+        board.enter(row, column, Some(4));
+        assert!(count(&board, CellStates::CHECKING) < expected);
+        assert!(!board.cell(row, column).states().contains(CellStates::CHECKING));
+        let after = (0..81).filter(|&i| board.candidates(i / 9, i % 9) & (1 << 4) != 0).count();
+        assert_eq!(after, count(&board, CellStates::CHECKING));
+        assert!(board.undo());
+        assert_eq!(expected, count(&board, CellStates::CHECKING));
+
+        board.select(None);
+        board.set_highlight(HighlightMode::None, None);
+        assert_eq!(0, count(&board, CellStates::SELECTED | CellStates::HIGH_LIGHT | CellStates::CHECKING));
+        assert_eq!(None, board.selected_cell());
+    }
+
+    // ptr 指向 AtomicU32, 可以在任意线程上计数
+    extern "C" fn count_update_atomic(ptr: *mut c_void, _: u32, _: u32) {
+        unsafe { &*(ptr as *const AtomicU32) }.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_callback_on_thread() {
+        let updates = std::sync::Arc::new(AtomicU32::new(0));
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        board.set_update_callback(count_update_atomic);
+        board.set_callback_ptr(std::sync::Arc::as_ptr(&updates) as *mut c_void);
+
+        // 回调随盘面一起在工作线程上执行
+        let main = std::thread::current().id();
+        let board = std::thread::spawn(move || {
+            assert_ne!(main, std::thread::current().id());
+            board.enter(0, 2, Some(4));
+            board
+        }).join().unwrap();
+
+        assert!(updates.load(Ordering::SeqCst) > 0);
+        assert_eq!(Some(4), board.cell(0, 2).selected());
+    }
+
+    #[test]
+    fn test_generate_on_thread() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Board>();
+
+        let mut board = Board::with_size(6, Variant::Diagonal).unwrap();
+        board.set_difficulty(Difficulty::Easy);
+        assert!(board.load(&".".repeat(36)));
+        let mut updates = 0u32;
+        board.set_update_callback(count_update_ptr);
+        board.set_callback_ptr(&mut updates as *mut u32 as *mut c_void);
+
+        // 副本在后台生成, 原盘面的回调不会被触发
+        let mut copy = board.new_like();
+        let copy = std::thread::spawn(move || {
+            assert!(copy.generate());
+            copy
+        }).join().unwrap();
+        assert_eq!(0, updates);
+        let puzzle = copy.export();
+
+        board.swap_puzzle(copy);
+        assert_eq!(36, updates);
+        assert_eq!(puzzle, board.export());
+        assert_eq!(Variant::Diagonal, board.variant());
+        assert_eq!(Difficulty::Easy, board.difficulty());
+        assert!(board.solution().is_some());
+        assert!(!board.undo());
+    }
+
+    #[test]
+    fn test_canonical() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        let canonical = board.canonical().unwrap();
+
+        // 转置并交换前两个宫组, 玩家填的数字不影响结果
+        let transformed: String = (0..81).map(|i| {
+            let (r, c) = (i / 9, i % 9);
+            let r = if r < 6 { (r + 3) % 6 } else { r };
+            PUZZLE.as_bytes()[c * 9 + r] as char
+        }).collect();
+        let mut other = Board::empty();
+        assert!(other.load(&transformed));
+        other.enter(0, 2, Some(1));
+        assert_eq!(Some(canonical.clone()), other.canonical());
+        assert!(board.is_equivalent(&other));
+
+        let mut board = Board::new(Variant::Diagonal);
+        assert!(board.load(PUZZLE));
+        assert!(board.canonical().is_none());
+    }
+
+    #[test]
+    fn test_minimal() {
+        let mut board = Board::empty();
+        let clues = board.generate_minimal().unwrap();
+        assert_eq!(clues, board.clue_count());
+        assert!(clues >= 17);
+        assert!(board.is_minimal());
+        assert!(board.solution().is_some());
+
+        // 普通题目多给一个答案中的数字就不是极小的
+        assert!(board.load(PUZZLE));
+        assert_eq!(30, board.clue_count());
+        let mut givens = board.givens();
+        let solution = board.solution().unwrap().to_vec();
+        let blank = givens.iter().position(|&v| v == 0).unwrap();
+        givens[blank] = solution[blank];
+        let puzzle: String = givens.iter().map(|v| v.to_string()).collect();
+        assert!(board.load(&puzzle));
+        assert!(!board.is_minimal());
+
+        // 多解的题目也不是极小的
+        assert!(board.load(&".".repeat(81)));
+        assert!(!board.is_minimal());
+
+        // 杀手数独和大盘面不支持, 取消时放弃
+        let mut board = Board::new(Variant::Killer);
+        assert_eq!(None, board.generate_minimal());
+        assert!(board.generate());
+        assert!(!board.is_minimal());
+        assert_eq!(None, Board::with_size(16, Variant::Standard).unwrap().generate_minimal());
+        let progress = Progress::new();
+        progress.cancel();
+        assert_eq!(None, Board::empty().generate_minimal_with_progress(&progress));
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        let solution = board.solution().unwrap().to_vec();
+        board.enter(0, 2, Some(4));
+        board.toggle_note(0, 3, 2);
+        let canonical = board.canonical();
+
+        let mut t = Transform::identity(3, 3);
+        assert!(t.rotate());
+        assert!(t.swap_bands(0, 1));
+        assert!(t.permute_digits(&[9, 8, 7, 6, 5, 4, 3, 2, 1]));
+        assert!(board.transform(&t));
+
+        assert_eq!(t.apply(&solution), board.solution().unwrap());
+        assert_eq!(canonical, board.canonical());
+        // (0, 2) 旋转到 (2, 8), 再换到第二个宫组
+        assert_eq!(Some(6), board.cell(5, 8).selected());
+        assert!(!board.cell(5, 8).is_prefilled());
+        assert_eq!(1 << 8, board.cell(0, 8).notes());
+        assert!(!board.is_wrong(5 * 9 + 8));
+
+        assert!(board.undo());
+        assert_eq!(0, board.cell(0, 8).notes());
+        assert!(board.undo());
+        assert_eq!(None, board.cell(5, 8).selected());
+
+        // 对角线变体不能交换宫组
+        let mut board = Board::new(Variant::Diagonal);
+        let mut t = Transform::identity(3, 3);
+        assert!(t.swap_bands(0, 1));
+        assert!(!board.transform(&t));
+        let mut t = Transform::identity(3, 3);
+        assert!(t.rotate());
+        assert!(board.transform(&t));
+        assert!(!board.transform(&Transform::identity(2, 3)));
+    }
+
+    #[test]
+    fn test_diagonal_candidates() {
+        let mut board = Board::new(Variant::Diagonal);
+        board.set(0, 0, Some(1));
+        assert!(!board.cell(8, 8).has_candidate(1));
+        assert!(board.cell(8, 1).has_candidate(1));
+
+        board.set(2, 6, Some(2));
+        board.set(6, 2, Some(2));
+        assert!(!board.check(2, 6));
+        assert!(!board.check(6, 2));
+        assert!(Board::empty().check(6, 2));
+    }
+
+    #[test]
+    fn test_diagonal_generate() {
+        let mut board = Board::new(Variant::Diagonal);
+        assert!(board.generate());
+        assert!(board.has_unique_solution());
+
+        let mut solution = Board::new(Variant::Diagonal);
+        for (idx, val) in board.solve().unwrap().into_iter().enumerate() {
+            solution.set(idx / 9, idx % 9, Some(val));
+        }
+        for i in 0..9 {
+            assert!(solution.check(i, i));
+            assert!(solution.check(i, 8 - i));
+        }
+    }
+
+    #[test]
+    fn test_jigsaw_regions() {
+        assert!(!Board::empty().set_regions(region::box_regions(9, 3, 3)));
+
+        let mut board = Board::new(Variant::Jigsaw);
+        assert!(!board.set_regions(vec![0; 81]));
+
+        // (2, 2) 划入第 1 区域, (0, 3) 划入第 0 区域
+        let mut regions = region::box_regions(9, 3, 3);
+        regions.swap(20, 3);
+        assert!(board.set_regions(regions));
+        assert_eq!(1, board.region(2, 2));
+
+        board.set(0, 0, Some(1));
+        board.set(1, 3, Some(5));
+        assert!(board.cell(2, 2).has_candidate(1));
+        assert!(!board.cell(2, 2).has_candidate(5));
+    }
+
+    #[test]
+    fn test_jigsaw_generate() {
+        let mut board = Board::new(Variant::Jigsaw);
+        assert!(board.generate());
+
+        assert!(region::is_valid_layout(board.regions(), 9));
+        assert!(board.has_unique_solution());
+    }
+
+    #[test]
+    fn test_killer_cages() {
+        let mut board = Board::new(Variant::Killer);
+        assert!(!Board::empty().set_cages(vec![]));
+        assert!(!board.set_cages(vec![Cage::new(vec![0, 1], 18)]));
+        assert!(!board.set_cages(vec![Cage::new(vec![0, 1], 3), Cage::new(vec![1, 2], 3)]));
+
+        assert!(board.set_cages(vec![Cage::new(vec![0, 1], 3), Cage::new(vec![2, 11, 20], 24)]));
+        assert_eq!(Some(1), board.cage_of(1, 2));
+        assert_eq!(None, board.cage_of(1, 1));
+        assert_eq!(0b110, board.candidates(0, 0));
+        assert_eq!(0b1110000000, board.candidates(2, 2));
+
+        board.set(0, 0, Some(2));
+        assert_eq!(0b10, board.candidates(0, 1));
+        assert!(!board.cell(0, 1).states().contains(CellStates::CONFLICT));
+
+        board.set(0, 1, Some(4));
+        assert!(board.cell(0, 0).states().contains(CellStates::CONFLICT));
+        assert!(board.cell(0, 1).states().contains(CellStates::CONFLICT));
+
+        board.set(0, 1, Some(1));
+        assert!(!board.cell(0, 0).states().contains(CellStates::CONFLICT));
+    }
+
+    #[test]
+    fn test_killer_generate() {
+        let mut board = Board::new(Variant::Killer);
+        assert!(board.generate());
+
+        assert!(board.values().iter().all(|&x| x == 0));
+        assert_eq!(405, board.cages().iter().map(|c| c.sum()).sum::<u32>());
+        assert!(board.has_unique_solution());
+    }
+
+    #[test]
+    fn test_box_shape() {
+        assert_eq!(Some((2, 2)), box_shape(4));
+        assert_eq!(Some((2, 3)), box_shape(6));
+        assert_eq!(Some((3, 3)), box_shape(9));
+        assert_eq!(Some((3, 4)), box_shape(12));
+        assert_eq!(Some((4, 4)), box_shape(16));
+        assert_eq!(None, box_shape(7));
+        assert_eq!(None, box_shape(25));
+        assert!(Board::with_size(7, Variant::Standard).is_none());
+    }
+
+    #[test]
+    fn test_sized_boards() {
+        for &size in [4, 6, 12].iter() {
+            let mut board = Board::with_size(size, Variant::Standard).unwrap();
+            assert!(board.generate());
+
+            assert!(board.has_unique_solution());
+            let solution = board.solve().unwrap();
+            assert_eq!(size * size, solution.len());
+            assert!(solution.iter().all(|&x| x >= 1 && x as usize <= size));
+        }
+
+        let mut board = Board::with_size(6, Variant::Diagonal).unwrap();
+        assert!(board.generate());
+        assert!(board.has_unique_solution());
+    }
+
+    #[test]
+    fn test_sized_candidates() {
+        let mut board = Board::with_size(16, Variant::Standard).unwrap();
+        assert_eq!(0x1fffe, board.candidates(0, 0));
+
+        board.set(0, 0, Some(16));
+        assert!(!board.cell(3, 3).has_candidate(16));
+        assert!(board.cell(4, 4).has_candidate(16));
+        assert!(!board.cell(15, 0).has_candidate(16));
+    }
+
+    #[test]
+    fn test_load_export() {
+        let puzzle = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut board = Board::empty();
+        assert!(board.load(puzzle));
+        assert_eq!(puzzle, board.export());
+        assert!(board.cell(0, 0).is_prefilled());
+        assert!(!board.cell(0, 2).has_candidate(5));
+        assert!(board.has_unique_solution());
+
+        assert!(!board.load("123"));
+        assert!(!Board::with_size(4, Variant::Standard).unwrap().load("5..............."));
+
+        let mut board = Board::with_size(16, Variant::Standard).unwrap();
+        let row = "G..A............";
+        assert!(board.load(&row.repeat(16)));
+        assert_eq!(Some(16), board.cell(1, 0).selected());
+        assert_eq!(Some(10), board.cell(0, 3).selected());
+        assert!(board.export().starts_with(row));
+        assert!(format!("{}", board).starts_with("+-------+-------+-------+-------+\n|G     A|"));
+
+        // 几乎为空的大盘面不会卡住, 只是没有答案
+        let mut board = Board::with_size(16, Variant::Standard).unwrap();
+        assert!(board.load(&format!("1{}", ".".repeat(255))));
+        assert!(board.solution().is_none());
+
+        // 很难的题目仍然能得到答案
+        let mut board = Board::empty();
+        assert!(board.load("1....7.9..3..2...8..96..5....53..9...1..8...26....4...3......1..4......7..7...3.."));
+        assert!(board.solution().is_some());
+    }
+
+    #[test]
+    fn test_clock() {
+        let time = ManualTime::new();
+        let mut board = Board::with_size(4, Variant::Standard).unwrap();
+        board.set_time_source(Box::new(time.clone()));
+
+        assert!(board.load("1..........3...."));
+        time.advance(3000);
+        board.pause();
+        time.advance(60_000);
+        assert_eq!(3000, board.elapsed_ms());
+
+        board.resume();
+        time.advance(250);
+        assert_eq!(3250, board.elapsed_ms());
+
+        // 重新开局清零
+        assert!(board.generate());
+        assert_eq!(0, board.elapsed_ms());
+        assert!(!board.is_paused());
+    }
+
+    #[test]
+    fn test_save_restore() {
+        let time = ManualTime::new();
+        let mut board = Board::empty();
+        board.set_time_source(Box::new(time.clone()));
+        assert!(board.load("53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"));
+        board.set(0, 2, Some(4));
+        board.toggle_note(0, 3, 6);
+        time.advance(12_345);
+        board.pause();
+
+        let data = board.save();
+        let mut restored = Board::from_save(&data).unwrap();
+        restored.set_time_source(Box::new(time.clone()));
+        time.advance(1000);
+        assert_eq!(12_345, restored.elapsed_ms());
+        assert!(restored.is_paused());
+        assert_eq!(board.export(), restored.export());
+        assert!(restored.cell(0, 0).is_prefilled());
+        assert!(!restored.cell(0, 2).is_prefilled());
+        assert!(!restored.cell(0, 3).has_candidate(4));
+        assert_eq!(1 << 6, restored.cell(0, 3).notes());
+        assert_eq!(1, restored.stats().notes);
+
+        let mut board = Board::new(Variant::Killer);
+        assert!(board.generate());
+        let restored = Board::from_save(&board.save()).unwrap();
+        assert_eq!(board.cages(), restored.cages());
+        assert!(!restored.is_paused());
+
+        let mut board = Board::with_size(6, Variant::Jigsaw).unwrap();
+        assert!(board.generate());
+        let restored = Board::from_save(&board.save()).unwrap();
+        assert_eq!(board.regions(), restored.regions());
+        assert_eq!(board.export(), restored.export());
+
+        assert!(Board::from_save("{}").is_none());
+        assert!(Board::from_save(&data.replace("\"givens\":\"53", "\"givens\":\"54")).is_none());
+    }
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    #[test]
+    fn test_mistakes() {
+        let time = ManualTime::new();
+        let mut board = Board::empty();
+        board.set_time_source(Box::new(time.clone()));
+        assert!(board.load(PUZZLE));
+        assert!(board.solution().is_some());
+
+        // 答案为 4, 与已填数字不冲突但仍算错误
+        assert!(board.enter(0, 2, Some(1)));
+        assert!(!board.enter(0, 2, Some(4)));
+        // 冲突
+        assert!(board.enter(0, 3, Some(5)));
+        // 题目给出的格子不能修改
+        assert!(!board.enter(0, 0, Some(1)));
+        assert_eq!(Some(5), board.cell(0, 0).selected());
+        // 超出范围的数字被忽略
+        assert!(!board.enter(0, 2, Some(0)));
+        assert!(!board.enter(0, 2, Some(10)));
+        assert_eq!(Some(4), board.cell(0, 2).selected());
+
+        time.advance(2 * 60_000);
+        let stats = board.stats();
+        assert_eq!(2, stats.mistakes);
+        assert_eq!(120_000, stats.elapsed_ms);
+        assert_eq!(10_000 - 2 * 500 - 2 * 100, stats.score);
+
+        // 多解的题目只检查冲突
+        assert!(board.load(&PUZZLE.replacen('5', ".", 1).replacen('3', ".", 1)));
+        assert!(board.solution().is_none());
+        assert_eq!(0, board.stats().mistakes);
+        assert!(!board.enter(0, 2, Some(1)));
+        assert!(board.enter(0, 3, Some(9)));
+    }
+
+    #[test]
+    fn test_notes_undo() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+
+        assert!(board.toggle_note(0, 2, 1));
+        assert!(board.toggle_note(0, 2, 4));
+        assert!(board.toggle_note(0, 2, 1));
+        assert!(!board.toggle_note(0, 0, 1));
+        assert_eq!(1 << 4, board.cell(0, 2).notes());
+
+        board.enter(0, 2, Some(4));
+        board.enter(0, 2, None);
+        assert!(board.undo());
+        assert_eq!(Some(4), board.cell(0, 2).selected());
+        assert!(board.undo());
+        assert_eq!(None, board.cell(0, 2).selected());
+        assert!(board.cell(0, 5).has_candidate(4));
+        assert!(board.undo());
+        assert_eq!(0b10010, board.cell(0, 2).notes());
+
+        let stats = board.stats();
+        assert_eq!(2, stats.notes);
+        assert_eq!(3, stats.undos);
+
+        assert!(board.undo());
+        assert!(board.undo());
+        assert!(!board.undo());
+    }
+
+    static UPDATES: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn count_update(_: *mut c_void, _: u32, _: u32) {
+        UPDATES.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // 与其他测试并行运行时不能共用 UPDATES, ptr 指向各自的计数器
+    extern "C" fn count_update_ptr(ptr: *mut c_void, _: u32, _: u32) {
+        unsafe { *(ptr as *mut u32) += 1 };
+    }
+
+    #[test]
+    fn test_fill_helpers() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        board.set_update_callback(count_update);
+
+        let filled = (0..81).filter(|&i| board.numbers[i].selected().is_some()).count();
+        UPDATES.store(0, Ordering::SeqCst);
+        assert_eq!(81 - filled, board.autofill_notes());
+        // 每个格子只通知一次
+        assert_eq!((81 - filled) as u32, UPDATES.load(Ordering::SeqCst));
+        assert_eq!(board.candidates(0, 2), board.cell(0, 2).notes());
+        assert_eq!(0, board.autofill_notes());
+        assert_eq!(1, board.stats().notes);
+
+        let naked = board.apply_naked_singles();
+        assert!(naked > 0);
+        let hidden = board.apply_hidden_singles();
+        assert!(hidden > 0);
+        for i in 0..81 {
+            if let Some(v) = board.numbers[i].selected() {
+                assert!(!board.is_wrong(i), "{} {}", i, v);
+            }
+        }
+
+        // 每个操作一次撤销
+        assert!(board.undo());
+        assert!(board.undo());
+        assert_eq!(filled, (0..81).filter(|&i| board.numbers[i].selected().is_some()).count());
+        assert!(board.undo());
+        assert_eq!(0, board.cell(0, 2).notes());
+        assert!(!board.undo());
+        assert_eq!(3, board.stats().undos);
+    }
+
+    #[test]
+    fn test_hint() {
+        let time = ManualTime::new();
+        let mut board = Board::empty();
+        board.set_time_source(Box::new(time.clone()));
+        assert!(board.load(PUZZLE));
+
+        board.enter(0, 3, Some(1));
+        assert_eq!(Some((0, 3)), board.hint());
+        assert_eq!(Some(6), board.cell(0, 3).selected());
+        assert_eq!(Some((0, 2)), board.hint());
+
+        while board.hint().is_some() {}
+        assert!(board.is_solved());
+        assert_eq!(51, board.stats().hints);
+
+        // 完成后停止计时
+        time.advance(1000);
+        assert_eq!(0, board.elapsed_ms());
+
+        let restored = Board::from_save(&board.save()).unwrap();
+        assert_eq!(board.stats(), restored.stats());
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let mut board = Board::empty();
+        board.set_difficulty(Difficulty::Easy);
+        assert!(board.generate());
+        // 会导致多解的格子不挖, 可能多留几个
+        assert!(board.values().iter().filter(|&&v| v != 0).count() >= 81 - 40);
+
+        let restored = Board::from_save(&board.save()).unwrap();
+        assert_eq!(Difficulty::Easy, restored.difficulty());
+    }
+
+    #[test]
+    fn test_daily() {
+        let date = Date::new(2024, 1, 1).unwrap();
+        let board = Board::daily(date, Difficulty::Easy);
+        assert_eq!("1.927354652...8.73...495.1....74.382....61.9....5...6795...6.24...9.7....7.15463.", board.export());
+        assert!(board.cell(0, 0).is_prefilled());
+        assert!(board.solution().is_some());
+        assert_eq!(Difficulty::Easy, board.difficulty());
+
+        let seed = board.seed().unwrap();
+        assert_eq!(Some(seed), Board::from_save(&board.save()).unwrap().seed());
+        assert_eq!(board.export(), Board::daily(date, Difficulty::Easy).export());
+        assert_ne!(board.export(), Board::daily(date, Difficulty::Medium).export());
+    }
+
+    #[test]
+    fn test_find_step() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+
+        let step = board.find_step(Technique::NakedSingle).unwrap();
+        let p = step.placements[0];
+        assert_eq!(board.solution().unwrap()[p.cell], p.digit);
+
+        // 杀手数独不能使用唯一解类技巧
+        let mut board = Board::new(Variant::Killer);
+        assert!(board.generate());
+        assert!(board.find_step(Technique::UniqueRectangle).is_none());
+        assert!(board.find_step(Technique::Bug1).is_none());
+    }
+
+    #[test]
+    fn test_generate_training() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+
+        let goal = TechniqueGoal::exactly_once(Technique::Jellyfish);
+        assert_eq!(Err(GenerateError::Timeout), board.generate_training(goal, Duration::from_millis(0)));
+        assert_eq!(PUZZLE, board.export());
+
+        // 大盘面上生成一次就要很久, 到时间时中途放弃
+        let mut large = Board::with_size(16, Variant::Standard).unwrap();
+        let start = Instant::now();
+        assert_eq!(Err(GenerateError::Timeout), large.generate_training(goal, Duration::from_millis(50)));
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let goal = TechniqueGoal { ceiling: Technique::NakedSingle, ..goal };
+        assert_eq!(Err(GenerateError::InvalidGoal), board.generate_training(goal, Duration::from_secs(1)));
+
+        let goal = TechniqueGoal { required: Technique::NakedPair, ceiling: Technique::Claiming, min_uses: 1, max_uses: 10 };
+        assert_eq!(Ok(()), board.generate_training(goal, Duration::from_secs(60)));
+        assert!(goal.accepts(board.candidate_grid()));
+        assert!(board.has_unique_solution());
+        assert_eq!(0, board.stats().hints);
+    }
+
+    #[test]
+    fn test_generate_with_clues() {
+        let mut board = Board::empty();
+        assert_eq!(Ok(()), board.generate_with_clues(24, 27, Duration::from_secs(60)));
+        assert!((24..=27).contains(&board.clue_count()));
+        assert!(board.has_unique_solution());
+        assert_eq!(board.solve().as_deref(), board.solution());
+
+        assert_eq!(Err(GenerateError::InvalidClueRange), board.generate_with_clues(30, 25, Duration::from_secs(1)));
+        assert_eq!(Err(GenerateError::InvalidClueRange), board.generate_with_clues(30, 82, Duration::from_secs(1)));
+
+        // 4x4 至少需要 4 个提示数
+        let mut board = Board::with_size(4, Variant::Standard).unwrap();
+        let before = board.export();
+        assert_eq!(Err(GenerateError::Timeout), board.generate_with_clues(0, 3, Duration::from_millis(200)));
+        assert_eq!(before, board.export());
+
+        // 生成中途到时间就放弃, 不会等到挖空结束
+        let mut board = Board::with_size(16, Variant::Standard).unwrap();
+        let start = Instant::now();
+        assert_eq!(Err(GenerateError::Timeout), board.generate_with_clues(0, 10, Duration::from_millis(50)));
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let mut board = Board::new(Variant::Killer);
+        assert_eq!(Err(GenerateError::InvalidClueRange), board.generate_with_clues(1, 5, Duration::from_secs(1)));
+    }
 }
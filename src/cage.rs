@@ -0,0 +1,275 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+// 杀手数独的笼子: 格子内数字不重复且和为 sum
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cage {
+    cells: Vec<usize>,
+    sum: u32,
+}
+
+impl Cage {
+    pub fn new(cells: Vec<usize>, sum: u32) -> Self {
+        Self { cells, sum }
+    }
+
+    pub fn cells(&self) -> &[usize] {
+        &self.cells
+    }
+
+    pub fn sum(&self) -> u32 {
+        self.sum
+    }
+
+    // 有重复数字, 或者已填数字之和超过/不足目标
+    pub fn is_broken(&self, values: &[u8]) -> bool {
+        let mut used = 0u32;
+        let mut total = 0;
+        let mut filled = 0;
+        for &cell in self.cells.iter() {
+            let v = values[cell];
+            if v == 0 {
+                continue;
+            }
+
+            if used & (1 << v) != 0 {
+                return true;
+            }
+            used |= 1 << v;
+            total += v as u32;
+            filled += 1;
+        }
+
+        total > self.sum || (filled == self.cells.len() && total != self.sum)
+    }
+
+    // 剩余空格还能填的数字
+    pub fn allowed_digits(&self, combinations: &Combinations, values: &[u8]) -> u32 {
+        let mut used = 0u32;
+        let mut total = 0;
+        let mut empty = 0;
+        for &cell in self.cells.iter() {
+            match values[cell] {
+                0 => empty += 1,
+                v => {
+                    used |= 1 << v;
+                    total += v as u32;
+                }
+            }
+        }
+
+        if total > self.sum {
+            return 0;
+        }
+
+        combinations.allowed(empty, self.sum - total, used)
+    }
+}
+
+// 按数字个数和总和分组的所有数字组合
+pub struct Combinations {
+    by_len_sum: Vec<Vec<Vec<u32>>>,
+}
+
+impl Combinations {
+    pub fn new(size: usize) -> Self {
+        let max_sum = size * (size + 1) / 2;
+        let mut by_len_sum = vec![vec![vec![]; max_sum + 1]; size + 1];
+
+        for subset in 0u32..(1 << size) {
+            let mask = subset << 1;
+            let sum: usize = (1..=size).filter(|&d| mask & (1 << d) != 0).sum();
+            by_len_sum[mask.count_ones() as usize][sum].push(mask);
+        }
+
+        Self { by_len_sum }
+    }
+
+    // 不含 exclude 中数字、个数为 len、和为 sum 的所有组合的并集
+    pub fn allowed(&self, len: usize, sum: u32, exclude: u32) -> u32 {
+        let sum = sum as usize;
+        if len >= self.by_len_sum.len() || sum >= self.by_len_sum[len].len() {
+            return 0;
+        }
+
+        self.by_len_sum[len][sum].iter()
+            .filter(|&&m| m & exclude == 0)
+            .fold(0, |acc, &m| acc | m)
+    }
+
+    pub fn is_possible(&self, len: usize, sum: u32) -> bool {
+        self.allowed(len, sum, 0) != 0
+    }
+}
+
+// 每个格子所在的笼子
+pub fn cage_map(cages: &[Cage], cell_count: usize) -> Vec<Option<usize>> {
+    let mut map = vec![None; cell_count];
+    for (idx, cage) in cages.iter().enumerate() {
+        for &cell in cage.cells() {
+            map[cell] = Some(idx);
+        }
+    }
+
+    map
+}
+
+// 笼子互不重叠, 和在可能的范围内
+pub fn is_valid_cages(cages: &[Cage], size: usize) -> bool {
+    let combinations = Combinations::new(size);
+    let mut seen = vec![false; size * size];
+
+    for cage in cages {
+        if cage.cells().is_empty() || !combinations.is_possible(cage.cells().len(), cage.sum()) {
+            return false;
+        }
+
+        for &cell in cage.cells() {
+            if cell >= seen.len() || seen[cell] {
+                return false;
+            }
+            seen[cell] = true;
+        }
+    }
+
+    true
+}
+
+fn neighbours(cell: usize, size: usize) -> Vec<usize> {
+    let row = cell / size;
+    let column = cell % size;
+
+    vec![
+        (row > 0).then(|| cell - size),
+        (row + 1 < size).then(|| cell + size),
+        (column > 0).then(|| cell - 1),
+        (column + 1 < size).then(|| cell + 1),
+    ].into_iter().flatten().collect()
+}
+
+// 把完整的解划分为随机的相连笼子, 笼子内数字互不相同
+pub fn random_cages<R: Rng>(rng: &mut R, solution: &[u8], size: usize) -> Vec<Cage> {
+    let mut assigned = vec![false; solution.len()];
+    let mut order: Vec<usize> = (0..solution.len()).collect();
+    order.shuffle(rng);
+
+    let mut cages = vec![];
+    for start in order {
+        if assigned[start] {
+            continue;
+        }
+
+        let target = *[1, 2, 2, 3, 3, 3, 4, 4, 5].choose(rng).unwrap();
+        let mut cells = vec![start];
+        let mut used = 1u32 << solution[start];
+        assigned[start] = true;
+
+        while cells.len() < target {
+            let frontier: Vec<usize> = cells.iter()
+                .flat_map(|&c| neighbours(c, size))
+                .filter(|&n| !assigned[n] && used & (1 << solution[n]) == 0)
+                .collect();
+
+            let next = match frontier.choose(rng) {
+                Some(&n) => n,
+                None => break,
+            };
+
+            assigned[next] = true;
+            used |= 1 << solution[next];
+            cells.push(next);
+        }
+
+        cells.sort_unstable();
+        let sum = cells.iter().map(|&c| solution[c] as u32).sum();
+        cages.push(Cage::new(cells, sum));
+    }
+
+    cages
+}
+
+// 拆分笼子: cell 单独成一个笼子, 剩下的格子按连通性分组
+pub fn split_cage(cages: &mut Vec<Cage>, cell: usize, solution: &[u8], size: usize) {
+    let idx = match cages.iter().position(|c| c.cells().contains(&cell)) {
+        Some(idx) => idx,
+        None => return,
+    };
+
+    let cage = cages.remove(idx);
+    cages.push(Cage::new(vec![cell], solution[cell] as u32));
+
+    let mut rest: Vec<usize> = cage.cells().iter().copied().filter(|&c| c != cell).collect();
+    while let Some(first) = rest.pop() {
+        let mut group = vec![first];
+        let mut i = 0;
+        while i < group.len() {
+            for n in neighbours(group[i], size) {
+                if let Some(pos) = rest.iter().position(|&c| c == n) {
+                    group.push(rest.swap_remove(pos));
+                }
+            }
+            i += 1;
+        }
+
+        group.sort_unstable();
+        let sum = group.iter().map(|&c| solution[c] as u32).sum();
+        cages.push(Cage::new(group, sum));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cage::*;
+
+    #[test]
+    fn test_combinations() {
+        let combinations = Combinations::new(9);
+
+        // 2 个数和为 3 只能是 1 + 2
+        assert_eq!(0b110, combinations.allowed(2, 3, 0));
+        // 3 个数和为 24 只能是 7 + 8 + 9
+        assert_eq!(0b1110000000, combinations.allowed(3, 24, 0));
+        assert_eq!(0, combinations.allowed(3, 24, 1 << 8));
+        assert!(!combinations.is_possible(2, 18));
+        assert!(combinations.is_possible(9, 45));
+    }
+
+    #[test]
+    fn test_cage_state() {
+        let combinations = Combinations::new(9);
+        let cage = Cage::new(vec![0, 1, 2], 10);
+        let mut values = vec![0u8; 81];
+
+        assert_eq!(0b11111110, cage.allowed_digits(&combinations, &values));
+        values[0] = 5;
+        assert_eq!(0b11110, cage.allowed_digits(&combinations, &values));
+        assert!(!cage.is_broken(&values));
+
+        values[1] = 6;
+        assert!(cage.is_broken(&values));
+        values[1] = 4;
+        values[2] = 2;
+        assert!(cage.is_broken(&values));
+        values[2] = 1;
+        assert!(!cage.is_broken(&values));
+    }
+
+    #[test]
+    fn test_random_cages() {
+        let solution: Vec<u8> = (0..81).map(|i| ((i / 9 * 3 + i / 27 + i % 9) % 9 + 1) as u8).collect();
+        let mut cages = random_cages(&mut rand::thread_rng(), &solution, 9);
+
+        assert!(is_valid_cages(&cages, 9));
+        assert_eq!(81, cages.iter().map(|c| c.cells().len()).sum::<usize>());
+        assert_eq!(405, cages.iter().map(|c| c.sum()).sum::<u32>());
+
+        let before = cages.len();
+        let big = cages.iter().find(|c| c.cells().len() > 1).unwrap().cells()[0];
+        split_cage(&mut cages, big, &solution, 9);
+        assert!(cages.len() > before);
+        assert!(cages.iter().any(|c| c.cells() == [big]));
+        assert!(is_valid_cages(&cages, 9));
+        assert_eq!(405, cages.iter().map(|c| c.sum()).sum::<u32>());
+    }
+}
@@ -0,0 +1,223 @@
+// 标准数独的规范形式: 在数字替换、宫组交换、组内行列交换和转置下字典序最小的盘面.
+// 等价的题目有相同的规范形式
+
+// 边长更大时行列排列数太多
+pub const MAX_CANONICAL_SIZE: usize = 9;
+
+pub fn canonical_form(values: &[u8], box_rows: usize, box_columns: usize) -> Option<Vec<u8>> {
+    let size = box_rows * box_columns;
+    if size > MAX_CANONICAL_SIZE || values.len() != size * size {
+        return None;
+    }
+
+    let mut best: Option<Vec<u8>> = None;
+    // 宫不是正方形时转置会改变宫的形状
+    let transposes: &[bool] = if box_rows == box_columns { &[false, true] } else { &[false] };
+    for &transpose in transposes {
+        let grid: Vec<u8> = if transpose {
+            (0..size * size).map(|i| values[(i % size) * size + i / size]).collect()
+        } else {
+            values.to_vec()
+        };
+
+        for columns in line_orders(size, box_columns) {
+            let mut search = Search {
+                grid: &grid,
+                size,
+                box_rows,
+                columns: &columns,
+                used: vec![false; size],
+                current: vec![],
+                best: best.take(),
+            };
+            search.rows(0, 0, &[0; MAX_CANONICAL_SIZE + 1], 1);
+            best = search.best;
+        }
+    }
+
+    best
+}
+
+// 所有保持宫组结构的行 (列) 排列: 宫组之间任意排列, 宫组内任意排列
+fn line_orders(size: usize, band: usize) -> Vec<Vec<usize>> {
+    let bands: Vec<usize> = (0..size / band).collect();
+    let inner: Vec<usize> = (0..band).collect();
+    let inner_orders = permutations(&inner);
+
+    let mut orders = vec![];
+    for band_order in permutations(&bands) {
+        let mut partial = vec![vec![]];
+        for &b in band_order.iter() {
+            partial = partial.iter().flat_map(|p: &Vec<usize>| {
+                inner_orders.iter().map(move |order| {
+                    let mut p = p.clone();
+                    p.extend(order.iter().map(|i| b * band + i));
+                    p
+                })
+            }).collect();
+        }
+        orders.extend(partial);
+    }
+
+    orders
+}
+
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+
+    let mut result = vec![];
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+        for mut p in permutations(&rest) {
+            p.insert(0, first);
+            result.push(p);
+        }
+    }
+
+    result
+}
+
+// 列顺序固定时, 逐行选择字典序最小的行, 相同时分别尝试
+struct Search<'a> {
+    grid: &'a [u8],
+    size: usize,
+    box_rows: usize,
+    columns: &'a [usize],
+    used: Vec<bool>,
+    current: Vec<u8>,
+    // 目前找到的最小盘面
+    best: Option<Vec<u8>>,
+}
+
+impl Search<'_> {
+    // 按 labels 重新编号第 row 行, 新出现的数字依次编号
+    fn relabel(&self, row: usize, labels: &[u8; MAX_CANONICAL_SIZE + 1], next: u8) -> (Vec<u8>, [u8; MAX_CANONICAL_SIZE + 1], u8) {
+        let mut labels = *labels;
+        let mut next = next;
+        let line = self.columns.iter().map(|&c| {
+            let v = self.grid[row * self.size + c] as usize;
+            if v != 0 && labels[v] == 0 {
+                labels[v] = next;
+                next += 1;
+            }
+            labels[v]
+        }).collect();
+
+        (line, labels, next)
+    }
+
+    // band 为当前宫组, 只在宫组的第一行之后有意义
+    fn rows(&mut self, slot: usize, band: usize, labels: &[u8; MAX_CANONICAL_SIZE + 1], next: u8) {
+        if slot == self.size {
+            self.best = Some(self.current.clone());
+            return;
+        }
+
+        // 宫组的第一行可以来自任意未使用的宫组, 其余行必须与上一行同组
+        let rows = self.box_rows;
+        let candidates: Vec<usize> = if slot.is_multiple_of(rows) {
+            (0..self.size).filter(|&r| !self.used[r]).collect()
+        } else {
+            (band * rows..(band + 1) * rows).filter(|&r| !self.used[r]).collect()
+        };
+
+        let lines: Vec<_> = candidates.iter().map(|&r| (r, self.relabel(r, labels, next))).collect();
+        let min = match lines.iter().map(|(_, (line, _, _))| line).min() {
+            Some(min) => min.clone(),
+            None => return,
+        };
+
+        let end = (slot + 1) * self.size;
+        self.current.extend_from_slice(&min);
+        let worse = self.best.as_ref().is_some_and(|b| self.current[..] > b[..end]);
+        if !worse {
+            // 同一宫组内完全相同的行交换后结果一样, 只需尝试一次
+            let grid = self.grid;
+            let mut tried: Vec<(usize, &[u8])> = vec![];
+            for (r, (line, labels, next)) in lines.iter() {
+                let raw = &grid[r * self.size..(r + 1) * self.size];
+                if *line != min || tried.contains(&(*r / rows, raw)) {
+                    continue;
+                }
+                tried.push((*r / rows, raw));
+
+                self.used[*r] = true;
+                self.rows(slot + 1, *r / rows, labels, *next);
+                self.used[*r] = false;
+            }
+        }
+        self.current.truncate(end - self.size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use rand::seq::SliceRandom;
+    use crate::canonical::*;
+
+    fn parse(s: &str) -> Vec<u8> {
+        s.bytes().map(|b| if b == b'.' { 0 } else { b - b'0' }).collect()
+    }
+
+    // 随机做一次等价变换
+    fn shuffle(values: &[u8]) -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        let orders = line_orders(9, 3);
+        let rows = orders.choose(&mut rng).unwrap();
+        let columns = orders.choose(&mut rng).unwrap();
+        let transpose = rng.gen_bool(0.5);
+        let mut digits: Vec<u8> = (1..=9).collect();
+        digits.shuffle(&mut rng);
+
+        (0..81).map(|i| {
+            let (r, c) = (rows[i / 9], columns[i % 9]);
+            let v = if transpose { values[c * 9 + r] } else { values[r * 9 + c] };
+            if v == 0 { 0 } else { digits[v as usize - 1] }
+        }).collect()
+    }
+
+    #[test]
+    fn test_line_orders() {
+        let orders = line_orders(9, 3);
+        assert_eq!(6 * 6 * 6 * 6, orders.len());
+        assert!(orders.contains(&vec![0, 1, 2, 3, 4, 5, 6, 7, 8]));
+        assert!(orders.contains(&vec![8, 6, 7, 0, 2, 1, 4, 3, 5]));
+        assert!(!orders.contains(&vec![3, 1, 2, 0, 4, 5, 6, 7, 8]));
+        assert_eq!(2 * 2 * 2, line_orders(4, 2).len());
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let puzzle = parse("53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79");
+        let canonical = canonical_form(&puzzle, 3, 3).unwrap();
+        for _ in 0..5 {
+            assert_eq!(canonical, canonical_form(&shuffle(&puzzle), 3, 3).unwrap());
+        }
+        assert_eq!(canonical, canonical_form(&canonical, 3, 3).unwrap());
+
+        // 数字从 1 开始按出现顺序编号, 第一行最小
+        assert_eq!(puzzle.iter().filter(|&&v| v != 0).count(), canonical.iter().filter(|&&v| v != 0).count());
+        assert!(canonical[..9] <= puzzle[..9]);
+
+        let other = parse("1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5");
+        assert_ne!(canonical, canonical_form(&other, 3, 3).unwrap());
+
+        assert_eq!(vec![0; 81], canonical_form(&[0; 81], 3, 3).unwrap());
+        assert!(canonical_form(&[0; 256], 4, 4).is_none());
+    }
+
+    #[test]
+    fn test_small_canonical_form() {
+        // 6x6 的宫为 2 行 3 列, 不能转置
+        let a = parse("123456456123231564564231312645645312");
+        let mut b = a.clone();
+        for r in 0..6 {
+            b.swap(r * 6, r * 6 + 2);
+        }
+        assert_eq!(canonical_form(&a, 2, 3), canonical_form(&b, 2, 3));
+    }
+}
@@ -20,20 +20,80 @@ bitflags! {
     }
 }
 
+// 高亮方式, 同一时间只有一种生效
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+pub enum HighlightMode {
+    None,
+    // 填了指定数字的格子, 设置 HIGH_LIGHT
+    Digit,
+    // 选中格子所在的行、列、宫等, 设置 HIGH_LIGHT
+    Peers,
+    // 候选数或笔记中含有指定数字的空格, 设置 CHECKING
+    Candidate,
+}
+
+impl HighlightMode {
+    // C 接口传入的值, 无效时返回 None
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(HighlightMode::None),
+            1 => Some(HighlightMode::Digit),
+            2 => Some(HighlightMode::Peers),
+            3 => Some(HighlightMode::Candidate),
+            _ => None,
+        }
+    }
+}
+
+// 支持的最大边长, 候选数用 u32 的第 1..=16 位表示
+pub const MAX_SIZE: usize = 16;
+
+// 1-9 之后用字母表示, A 为 10
+pub fn digit_to_char(val: u8) -> char {
+    match val {
+        1..=9 => (b'0' + val) as char,
+        _ => (b'A' + val - 10) as char,
+    }
+}
+
+pub fn char_to_digit(c: char) -> Option<u8> {
+    match c {
+        '1'..='9' => Some(c as u8 - b'0'),
+        'A'..='G' => Some(c as u8 - b'A' + 10),
+        'a'..='g' => Some(c as u8 - b'a' + 10),
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
 pub struct Cell {
     selected: Option<u8>,
-    candidate: [i8; 10],
+    size: u8,
+    candidate: [i8; MAX_SIZE + 1],
     states: CellStates,
+    // 玩家的笔记, 与候选数相同的位掩码
+    notes: u32,
+    // 通过推理排除的候选数
+    excluded: u32,
 }
 
 impl Cell {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(size: usize) -> Self {
+        debug_assert!(size <= MAX_SIZE);
+
+        let mut cell = Self {
             selected: None,
-            candidate: [1i8; 10],
+            size: size as u8,
+            candidate: [0i8; MAX_SIZE + 1],
             states: CellStates::NONE,
-        }
+            notes: 0,
+            excluded: 0,
+        };
+        cell.reset_candidate();
+
+        cell
     }
 
     pub fn states(&self) -> CellStates {
@@ -44,7 +104,7 @@ impl Cell {
         self.states = states;
 
         if (states & CellStates::PRE_FILLED) == CellStates::PRE_FILLED {
-            self.candidate = [0i8; 10];
+            self.candidate = [0i8; MAX_SIZE + 1];
         }
     }
 
@@ -56,17 +116,33 @@ impl Cell {
         self.selected = val;
     }
 
+    pub fn notes(&self) -> u32 {
+        self.notes
+    }
+
+    pub fn set_notes(&mut self, notes: u32) {
+        self.notes = notes;
+    }
+
+    pub fn excluded(&self) -> u32 {
+        self.excluded
+    }
+
+    pub fn set_excluded(&mut self, excluded: u32) {
+        self.excluded = excluded;
+    }
+
     pub fn has_candidate(&self, candidate: u8) -> bool {
-        debug_assert!(candidate > 0 && candidate <= 9);
+        debug_assert!(candidate > 0 && candidate <= self.size);
 
-        self.candidate[candidate as usize] > 0
+        self.candidate[candidate as usize] > 0 && self.excluded & (1 << candidate) == 0
     }
 
     pub fn best_candidates(&self) -> Vec<u8> {
         let mut r = vec![];
         let low = self.selected.unwrap_or(0) as usize + 1;
 
-        for i in low..=9 {
+        for i in low..=self.size as usize {
             if self.candidate[i] > 0 {
                 r.push(i as u8);
             }
@@ -94,7 +170,7 @@ impl Cell {
             return false;
         }
 
-        for i in 1..=9 {
+        for i in 1..=self.size as usize {
             if self.candidate[i] > 0 {
                 return false;
             }
@@ -104,19 +180,22 @@ impl Cell {
     }
 
     pub fn reset_candidate(&mut self) {
-        self.candidate = [1i8; 10];
+        self.candidate = [0i8; MAX_SIZE + 1];
+        for i in 1..=self.size as usize {
+            self.candidate[i] = 1;
+        }
     }
 
     pub fn candidate_u32(&self) -> u32 {
         let mut flags: u32 = 0;
 
-        for i in 1u32..=9 {
+        for i in 1u32..=self.size as u32 {
             if self.candidate[i as usize] > 0 {
                 flags |= 1 << i;
             }
         }
 
-        flags
+        flags & !self.excluded
     }
 
     pub fn is_prefilled(&self) -> bool {
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+use std::time::Instant;
+
+// 计时用的时间源, 测试时可以替换为手动推进的时间
+pub trait TimeSource: Send {
+    // 单调递增的毫秒数
+    fn now_ms(&self) -> u64;
+}
+
+pub struct SystemTime {
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+    origin: Instant,
+    // 浏览器中没有 Instant, 使用 Date.now()
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    origin: f64,
+}
+
+impl SystemTime {
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+    pub fn new() -> Self {
+        Self { origin: Instant::now() }
+    }
+
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn new() -> Self {
+        Self { origin: js_sys::Date::now() }
+    }
+}
+
+impl Default for SystemTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for SystemTime {
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+    fn now_ms(&self) -> u64 {
+        self.origin.elapsed().as_millis() as u64
+    }
+
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    fn now_ms(&self) -> u64 {
+        (js_sys::Date::now() - self.origin).max(0.0) as u64
+    }
+}
+
+// 只有调用 advance 时才会前进, 克隆出的实例共享同一时间
+#[derive(Clone, Default)]
+pub struct ManualTime {
+    now: Arc<AtomicU64>,
+}
+
+impl ManualTime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now_ms(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+// 游戏计时器, 暂停期间不计时
+pub struct Clock {
+    source: Box<dyn TimeSource>,
+    // 本次开始/继续计时的时刻, 暂停时为 None
+    running_since: Option<u64>,
+    // 之前各段累计的时间
+    elapsed: u64,
+}
+
+impl Clock {
+    pub fn new(source: Box<dyn TimeSource>) -> Self {
+        Self {
+            source,
+            running_since: None,
+            elapsed: 0,
+        }
+    }
+
+    // 更换时间源, 已经累计的时间保留
+    pub fn set_source(&mut self, source: Box<dyn TimeSource>) {
+        let running = !self.is_paused();
+        self.pause();
+        self.source = source;
+        if running {
+            self.resume();
+        }
+    }
+
+    // 清零并开始计时
+    pub fn start(&mut self) {
+        self.elapsed = 0;
+        self.running_since = Some(self.source.now_ms());
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.elapsed += self.source.now_ms().saturating_sub(since);
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(self.source.now_ms());
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        let running = self.running_since.map_or(0, |since| self.source.now_ms().saturating_sub(since));
+
+        self.elapsed + running
+    }
+
+    // 从存档恢复
+    pub fn restore(&mut self, elapsed: u64, paused: bool) {
+        self.elapsed = elapsed;
+        self.running_since = (!paused).then(|| self.source.now_ms());
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(Box::new(SystemTime::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::*;
+
+    #[test]
+    fn test_pause_resume() {
+        let time = ManualTime::new();
+        let mut clock = Clock::new(Box::new(time.clone()));
+        assert!(clock.is_paused());
+        assert_eq!(0, clock.elapsed_ms());
+
+        clock.start();
+        time.advance(1500);
+        assert_eq!(1500, clock.elapsed_ms());
+
+        clock.pause();
+        clock.pause();
+        time.advance(10_000);
+        assert!(clock.is_paused());
+        assert_eq!(1500, clock.elapsed_ms());
+
+        clock.resume();
+        clock.resume();
+        time.advance(500);
+        assert_eq!(2000, clock.elapsed_ms());
+
+        clock.start();
+        assert_eq!(0, clock.elapsed_ms());
+    }
+
+    #[test]
+    fn test_restore() {
+        let time = ManualTime::new();
+        let mut clock = Clock::new(Box::new(time.clone()));
+
+        clock.restore(42_000, true);
+        time.advance(100);
+        assert_eq!(42_000, clock.elapsed_ms());
+
+        clock.restore(42_000, false);
+        time.advance(100);
+        assert_eq!(42_100, clock.elapsed_ms());
+
+        // 换时间源不影响已累计的时间
+        let other = ManualTime::new();
+        clock.set_source(Box::new(other.clone()));
+        other.advance(900);
+        assert_eq!(43_000, clock.elapsed_ms());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::difficulty::Difficulty;
+use crate::rng::StableRng;
+use crate::solver::Solver;
+use crate::units::Units;
+
+// 每日题目算法的版本, 修改生成过程时需要增加版本号并更新测试
+pub const DAILY_VERSION: u64 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Date {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: u32, month: u32, day: u32) -> Option<Self> {
+        let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+
+        (day >= 1 && day <= days).then_some(Self { year, month, day })
+    }
+}
+
+// 每日题目的种子, 同一天不同难度的题目互不相关
+pub fn daily_seed(date: Date, difficulty: Difficulty) -> u64 {
+    let day = (date.year as u64 * 100 + date.month as u64) * 100 + date.day as u64;
+
+    (DAILY_VERSION << 56) ^ (day << 8) ^ difficulty as u64
+}
+
+// 固定的挖空数, 不随 Difficulty 的调整而变化
+fn removed_cells(difficulty: Difficulty) -> usize {
+    match difficulty {
+        Difficulty::Easy => 40,
+        Difficulty::Medium => 50,
+        Difficulty::Hard => 56,
+        Difficulty::Expert => 60,
+    }
+}
+
+// 标准 9x9 题目, 只依赖 StableRng 和求解器, 不受 Board 生成过程改动的影响.
+// 求解器用到 rand 的 shuffle 和 choose, 升级 rand 后需要确认 test_locked_puzzles 不变
+pub fn daily_puzzle(seed: u64, difficulty: Difficulty) -> Vec<u8> {
+    let mut rng = StableRng::new(seed);
+    let units = Units::standard();
+
+    // 随机回溯得到的解. 走进死胡同时继续用同一个 rng 重新搜索, 结果仍然只由种子决定
+    let blank = [0; 81];
+    let mut values = loop {
+        let solution = Solver::new(&units, &blank).and_then(|s| s.node_limit(81 * 20).solve_random(&mut rng));
+        if let Some(solution) = solution {
+            break solution;
+        }
+    };
+
+    let mut cells: Vec<usize> = (0..81).collect();
+    rng.shuffle(&mut cells);
+
+    let mut removed = 0;
+    for pick in cells {
+        if removed == removed_cells(difficulty) {
+            break;
+        }
+
+        let backup = values[pick];
+        values[pick] = 0;
+        if Solver::new(&units, &values).is_some_and(|mut s| s.count_solutions(2) == 1) {
+            removed += 1;
+        } else {
+            values[pick] = backup;
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::daily::*;
+
+    #[test]
+    fn test_date() {
+        assert!(Date::new(2024, 2, 29).is_some());
+        assert!(Date::new(2023, 2, 29).is_none());
+        assert!(Date::new(1900, 2, 29).is_none());
+        assert!(Date::new(2000, 2, 29).is_some());
+        assert!(Date::new(2024, 4, 31).is_none());
+        assert!(Date::new(2024, 13, 1).is_none());
+        assert!(Date::new(2024, 1, 0).is_none());
+    }
+
+    #[test]
+    fn test_daily_seed() {
+        let date = Date::new(2024, 1, 1).unwrap();
+        assert_ne!(daily_seed(date, Difficulty::Easy), daily_seed(date, Difficulty::Hard));
+        assert_ne!(daily_seed(date, Difficulty::Easy), daily_seed(Date::new(2024, 1, 2).unwrap(), Difficulty::Easy));
+    }
+
+    fn puzzle(year: u32, month: u32, day: u32, difficulty: Difficulty) -> String {
+        let date = Date::new(year, month, day).unwrap();
+        daily_puzzle(daily_seed(date, difficulty), difficulty).iter().map(|v| (b'0' + v) as char).collect()
+    }
+
+    // 已经发布过的题目不能改变
+    #[test]
+    fn test_locked_puzzles() {
+        assert_eq!("109273546520008073000495010000740382000061090000500067950006024000907000070154630", puzzle(2024, 1, 1, Difficulty::Easy));
+        assert_eq!("000002090040005007000000100307084000000900600600100500000000000905000280100040700", puzzle(2024, 1, 1, Difficulty::Expert));
+        assert_eq!("000600050000007008860513000000000000609400700320100900200759380705040020080002074", puzzle(2025, 6, 15, Difficulty::Medium));
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Expert];
+
+    // C 接口传入的值, 无效时返回 None
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Difficulty::ALL.get(value as usize).copied()
+    }
+
+    // 生成时挖空的格子数, 9x9 下分别为 40/50/56/60
+    pub fn removed_cells(self, cell_count: usize) -> usize {
+        let per_81 = match self {
+            Difficulty::Easy => 40,
+            Difficulty::Medium => 50,
+            Difficulty::Hard => 56,
+            Difficulty::Expert => 60,
+        };
+
+        cell_count * per_81 / 81
+    }
+}
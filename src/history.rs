@@ -0,0 +1,262 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::difficulty::Difficulty;
+use crate::variant::Variant;
+
+// 统计的范围, 为 None 的条件不限制
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatsFilter {
+    pub difficulty: Option<Difficulty>,
+    pub variant: Option<Variant>,
+    pub size: Option<usize>,
+}
+
+impl StatsFilter {
+    // 某一种题目: 指定难度、变体和尺寸
+    pub fn game(difficulty: Difficulty, variant: Variant, size: usize) -> Self {
+        Self { difficulty: Some(difficulty), variant: Some(variant), size: Some(size) }
+    }
+
+    fn matches(&self, record: &GameRecord) -> bool {
+        self.difficulty.is_none_or(|d| d == record.difficulty)
+            && self.variant.is_none_or(|v| v == record.variant)
+            && self.size.is_none_or(|s| s == record.size)
+    }
+}
+
+// 一局结束后的记录
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub difficulty: Difficulty,
+    pub variant: Variant,
+    pub size: usize,
+    // 随机生成的题目没有种子
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub won: bool,
+    pub elapsed_ms: u64,
+    pub mistakes: u32,
+    pub hints: u32,
+    pub score: u32,
+    // unix 时间戳, 秒
+    pub finished_at: u64,
+}
+
+impl GameRecord {
+    pub fn from_board(board: &Board, won: bool) -> Self {
+        let stats = board.stats();
+        let finished_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+        Self {
+            difficulty: board.difficulty(),
+            variant: board.variant(),
+            size: board.size(),
+            seed: board.seed(),
+            won,
+            elapsed_ms: stats.elapsed_ms,
+            mistakes: stats.mistakes,
+            hints: stats.hints,
+            score: stats.score,
+            finished_at,
+        }
+    }
+}
+
+// 汇总统计, 时间只统计获胜的局, 没有数据时为 0
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub played: u32,
+    pub won: u32,
+    pub best_ms: u64,
+    pub average_ms: u64,
+    // 最近连续获胜的局数
+    pub current_streak: u32,
+    pub best_streak: u32,
+}
+
+// 历史记录, 每次添加后写回文件
+pub struct History {
+    path: Option<PathBuf>,
+    records: Vec<GameRecord>,
+}
+
+impl History {
+    // 文件不存在时为空记录
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let records = match fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self { path: Some(path), records })
+    }
+
+    // 不写文件
+    pub fn in_memory() -> Self {
+        Self { path: None, records: vec![] }
+    }
+
+    pub fn records(&self) -> &[GameRecord] {
+        &self.records
+    }
+
+    // 写文件失败时不修改内存中的记录, 与文件保持一致
+    pub fn record(&mut self, record: GameRecord) -> io::Result<()> {
+        self.records.push(record);
+        let result = self.save(&self.records);
+        if result.is_err() {
+            self.records.pop();
+        }
+
+        result
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.save(&[])?;
+        self.records.clear();
+
+        Ok(())
+    }
+
+    // 先写临时文件再改名, 避免写到一半时损坏记录
+    fn save(&self, records: &[GameRecord]) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string(records)?)?;
+        fs::rename(&tmp, path)
+    }
+
+    // 只统计 filter 选中的记录. 不同尺寸和变体的用时差别很大, 比较用时时应当指定
+    pub fn stats(&self, filter: StatsFilter) -> PlayerStats {
+        let mut stats = PlayerStats::default();
+        let mut total_ms = 0;
+
+        for record in self.records.iter().filter(|r| filter.matches(r)) {
+            stats.played += 1;
+            if !record.won {
+                stats.current_streak = 0;
+                continue;
+            }
+
+            stats.won += 1;
+            stats.current_streak += 1;
+            stats.best_streak = stats.best_streak.max(stats.current_streak);
+            if stats.best_ms == 0 || record.elapsed_ms < stats.best_ms {
+                stats.best_ms = record.elapsed_ms;
+            }
+            total_ms += record.elapsed_ms;
+        }
+
+        if stats.won > 0 {
+            stats.average_ms = total_ms / stats.won as u64;
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::*;
+
+    fn record(difficulty: Difficulty, won: bool, elapsed_ms: u64) -> GameRecord {
+        GameRecord {
+            difficulty,
+            variant: Variant::Standard,
+            size: 9,
+            seed: None,
+            won,
+            elapsed_ms,
+            mistakes: 0,
+            hints: 0,
+            score: 0,
+            finished_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut history = History::in_memory();
+        assert_eq!(PlayerStats::default(), history.stats(StatsFilter::default()));
+
+        for &(won, ms) in [(true, 300_000), (true, 200_000), (false, 50_000), (true, 400_000)].iter() {
+            history.record(record(Difficulty::Easy, won, ms)).unwrap();
+        }
+        history.record(record(Difficulty::Hard, true, 900_000)).unwrap();
+
+        let easy = history.stats(StatsFilter::game(Difficulty::Easy, Variant::Standard, 9));
+        assert_eq!(4, easy.played);
+        assert_eq!(3, easy.won);
+        assert_eq!(200_000, easy.best_ms);
+        assert_eq!(300_000, easy.average_ms);
+        assert_eq!(1, easy.current_streak);
+        assert_eq!(2, easy.best_streak);
+
+        let all = history.stats(StatsFilter::default());
+        assert_eq!(5, all.played);
+        assert_eq!(2, all.current_streak);
+        assert_eq!(0, history.stats(StatsFilter::game(Difficulty::Expert, Variant::Standard, 9)).played);
+    }
+
+    #[test]
+    fn test_stats_filter() {
+        let mut history = History::in_memory();
+        history.record(record(Difficulty::Easy, true, 300_000)).unwrap();
+        history.record(GameRecord { size: 4, ..record(Difficulty::Easy, true, 20_000) }).unwrap();
+        history.record(GameRecord { variant: Variant::Killer, ..record(Difficulty::Easy, true, 900_000) }).unwrap();
+
+        // 4x4 和杀手数独的用时不影响标准 9x9
+        let standard = history.stats(StatsFilter::game(Difficulty::Easy, Variant::Standard, 9));
+        assert_eq!(1, standard.played);
+        assert_eq!(300_000, standard.best_ms);
+        assert_eq!(300_000, standard.average_ms);
+
+        let small = history.stats(StatsFilter { size: Some(4), ..StatsFilter::default() });
+        assert_eq!(20_000, small.best_ms);
+
+        let easy = history.stats(StatsFilter { difficulty: Some(Difficulty::Easy), ..StatsFilter::default() });
+        assert_eq!(3, easy.played);
+        assert_eq!(20_000, easy.best_ms);
+        assert_eq!(1, history.stats(StatsFilter { variant: Some(Variant::Killer), ..StatsFilter::default() }).played);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = std::env::temp_dir().join(format!("sudoku-history-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = History::open(&path).unwrap();
+        assert!(history.records().is_empty());
+        history.record(record(Difficulty::Medium, true, 123_456)).unwrap();
+
+        let history = History::open(&path).unwrap();
+        assert_eq!(1, history.records().len());
+        assert_eq!(123_456, history.stats(StatsFilter::game(Difficulty::Medium, Variant::Standard, 9)).best_ms);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(History::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_save_failure() {
+        // 目录不存在, 打开时为空记录, 写入会失败
+        let path = std::env::temp_dir().join(format!("sudoku-missing-{}", std::process::id())).join("history.json");
+        let mut history = History::open(&path).unwrap();
+        assert!(history.record(record(Difficulty::Easy, true, 1000)).is_err());
+        assert!(history.records().is_empty());
+        assert_eq!(0, history.stats(StatsFilter::default()).played);
+    }
+}
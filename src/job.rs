@@ -0,0 +1,201 @@
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::board::{Board, CallbackPtr};
+
+// 生成进度和取消标志, 可以在线程间共享
+#[derive(Debug, Default)]
+pub struct Progress {
+    // 千分比
+    permille: AtomicU32,
+    cancelled: AtomicBool,
+    // 超过截止时间视为已取消
+    deadline: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self { deadline: Some(deadline), ..Self::default() }
+    }
+
+    // 0.0 到 1.0
+    pub fn get(&self) -> f32 {
+        self.permille.load(Ordering::Relaxed) as f32 / 1000.0
+    }
+
+    pub fn set(&self, value: f32) {
+        self.permille.store((value.clamp(0.0, 1.0) * 1000.0) as u32, Ordering::Relaxed);
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+// 完成回调, 在工作线程上调用
+pub type JobCallback = extern "C" fn(*mut c_void);
+
+#[derive(Default)]
+struct JobState {
+    progress: Progress,
+    finished: AtomicBool,
+    // 取消或已被取走时为 None
+    result: Mutex<Option<Board>>,
+}
+
+// 在工作线程上生成题目, 所有方法都可以在任意线程调用
+pub struct GenerateJob {
+    state: Arc<JobState>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl GenerateJob {
+    // 按 template 的尺寸、变体和难度生成, 结束后 (包括取消) 调用 on_done
+    pub fn start<F: FnOnce() + Send + 'static>(template: &Board, on_done: F) -> Self {
+        let state = Arc::new(JobState::default());
+        let mut board = template.new_like();
+
+        let shared = state.clone();
+        let handle = thread::spawn(move || {
+            // 生成时 panic 也要标记完成并调用 on_done, 结果视为失败
+            let generated = panic::catch_unwind(AssertUnwindSafe(|| board.generate_with_progress(&shared.progress)));
+            if generated.unwrap_or(false) {
+                *shared.result.lock().unwrap() = Some(board);
+            }
+            shared.finished.store(true, Ordering::SeqCst);
+            on_done();
+        });
+
+        Self { state, handle: Some(handle) }
+    }
+
+    // 使用 C 回调, ptr 原样传回
+    pub fn start_with_callback(template: &Board, ptr: *mut c_void, cb: Option<JobCallback>) -> Self {
+        let ptr = CallbackPtr(ptr);
+        Self::start(template, move || {
+            if let Some(cb) = cb {
+                cb(ptr.0);
+            }
+        })
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.state.progress.get()
+    }
+
+    pub fn cancel(&self) {
+        self.state.progress.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.progress.is_cancelled()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::SeqCst)
+    }
+
+    // 不阻塞, 未完成、已取消或已经取走时返回 None
+    pub fn take(&self) -> Option<Board> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        self.state.result.lock().unwrap().take()
+    }
+
+    // 阻塞到生成结束
+    pub fn wait(mut self) -> Option<Board> {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+
+        self.take()
+    }
+}
+
+// 释放时取消并等待工作线程退出
+impl Drop for GenerateJob {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.cancel();
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use crate::difficulty::Difficulty;
+    use crate::job::*;
+
+    #[test]
+    fn test_progress() {
+        let progress = Progress::new();
+        assert_eq!(0.0, progress.get());
+        progress.set(0.5);
+        assert_eq!(0.5, progress.get());
+        progress.set(2.0);
+        assert_eq!(1.0, progress.get());
+
+        assert!(!progress.is_cancelled());
+        progress.cancel();
+        assert!(progress.is_cancelled());
+
+        // 已取消时不会生成, 盘面保持不变
+        let mut board = Board::empty();
+        let puzzle = "..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..";
+        assert!(board.load(puzzle));
+        assert!(!board.generate_with_progress(&progress));
+        assert_eq!(puzzle, board.export());
+
+        let progress = Progress::with_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(!progress.is_cancelled());
+        let progress = Progress::with_deadline(Instant::now());
+        assert!(progress.is_cancelled());
+        assert!(!board.generate_with_progress(&progress));
+    }
+
+    #[test]
+    fn test_generate_job() {
+        let mut template = Board::empty();
+        template.set_difficulty(Difficulty::Easy);
+
+        let (sender, receiver) = mpsc::channel();
+        let job = GenerateJob::start(&template, move || sender.send(()).unwrap());
+        receiver.recv().unwrap();
+
+        assert!(job.is_finished());
+        assert_eq!(1.0, job.progress());
+        let board = job.take().unwrap();
+        assert_eq!(Difficulty::Easy, board.difficulty());
+        assert!(board.has_unique_solution());
+        assert!(job.take().is_none());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let job = GenerateJob::start(&Board::empty(), || {});
+        job.cancel();
+        assert!(job.is_cancelled());
+
+        // 取消前可能已经生成完成
+        if let Some(board) = job.wait() {
+            assert!(board.has_unique_solution());
+        }
+    }
+}
@@ -1,92 +1,1092 @@
 
+#[macro_use]
+mod logging;
+
 mod board;
+mod cage;
+mod canonical;
 mod cell;
+mod clock;
+mod daily;
+mod difficulty;
+mod history;
+mod job;
+mod pool;
+mod region;
+mod rng;
+mod save;
+mod score;
+mod solver;
+mod strategy;
+mod trace;
+mod training;
+mod transform;
+mod units;
+mod variant;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 pub use board::Board;
-pub use cell::CellStates;
-use std::os::raw::c_void;
+pub use cage::Cage;
+pub use cell::{CellStates, HighlightMode};
+pub use clock::{Clock, ManualTime, SystemTime, TimeSource};
+pub use daily::Date;
+pub use difficulty::Difficulty;
+pub use history::{GameRecord, History, PlayerStats, StatsFilter};
+pub use job::{GenerateJob, JobCallback, Progress};
+pub use logging::{Level, LogCallback};
+pub use pool::PuzzlePool;
+pub use rng::StableRng;
+pub use score::{GameStats, ScoreConfig};
+pub use strategy::{Candidate, Grid, Step, Technique};
+pub use trace::{Replay, SolveTrace};
+pub use training::{GenerateError, TechniqueGoal};
+pub use transform::{Transform, TransformKind};
+pub use units::{Unit, UnitKind, Units};
+pub use variant::Variant;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmBoard;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr::null_mut;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// 线程模型:
+// - 所有函数都可以在任意线程调用, 不同的 Board 之间互不影响
+// - 同一个 Board 同一时间只能在一个线程上使用, 需要共享时由宿主加锁
+// - 更新回调在调用函数的线程上同步执行, 日志回调可能来自任意线程.
+//   Board 被移到其他线程后, 更新回调和它的 ptr 在新线程上使用, 宿主必须保证 ptr 指向的数据
+//   可以跨线程访问 (相当于 Rust 的 Send), 否则只在创建 Board 的线程上使用它
+// - 在后台线程生成题目: 用 sudoku_new_like 创建副本并在后台 sudoku_generate,
+//   完成后回到使用原盘面的线程调用 sudoku_swap_puzzle
+// - 也可以用 sudoku_generate_start 在工作线程上生成, job 相关函数可以在任意线程调用
+
+// 与 WasmBoard 相同, 宿主传入的行列需要先检查. 超出盘面时读取格子返回 0,
+// 修改格子不做任何操作并返回 false
+fn contains(board: &Board, row: u32, column: u32) -> bool {
+    let size = board.size() as u32;
+    row < size && column < size
+}
 
 #[no_mangle]
-pub extern fn sudoku_new() -> *mut Board {
+pub extern "C" fn sudoku_new() -> *mut Board {
     let board = Box::new(Board::empty());
 
     Box::into_raw(board)
 }
 
+// variant 为 Variant 的值, 无效时返回 NULL
+#[no_mangle]
+pub extern "C" fn sudoku_new_variant(variant: u32) -> *mut Board {
+    match Variant::from_u32(variant) {
+        Some(variant) => Box::into_raw(Box::new(Board::new(variant))),
+        None => null_mut(),
+    }
+}
+
+// 不支持的尺寸或无效的 variant 返回 NULL
+#[no_mangle]
+pub extern "C" fn sudoku_new_with_size(size: u32, variant: u32) -> *mut Board {
+    match Variant::from_u32(variant).and_then(|variant| Board::with_size(size as usize, variant)) {
+        Some(board) => Box::into_raw(Box::new(board)),
+        None => null_mut(),
+    }
+}
+
+// 每日题目, 日期或难度无效时返回 NULL
+#[no_mangle]
+pub extern "C" fn sudoku_new_daily(year: u32, month: u32, day: u32, difficulty: u32) -> *mut Board {
+    match (Date::new(year, month, day), Difficulty::from_u32(difficulty)) {
+        (Some(date), Some(difficulty)) => Box::into_raw(Box::new(Board::daily(date, difficulty))),
+        _ => null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `board` must be null or a pointer returned by `sudoku_new` that has not been freed yet.
 #[no_mangle]
-pub unsafe extern fn sudoku_free(board: *mut Board) {
+pub unsafe extern "C" fn sudoku_free(board: *mut Board) {
     if !board.is_null() {
-        Box::from_raw(board);
+        drop(Box::from_raw(board));
+    }
+}
+
+/// 相同尺寸、变体和难度的空盘面, 不带回调, 可以移动到其他线程使用
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_new_like(board: *mut Board) -> *mut Board {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    Box::into_raw(Box::new(board.new_like()))
+}
+
+/// 使用 `other` 的题目开始新的一局, `other` 随后被释放.
+/// `other` 为 NULL 或与 `board` 相同时不做任何操作并返回 false.
+///
+/// # Safety
+///
+/// `other` must be null or a pointer returned by `sudoku_new*` that has not been freed yet
+/// and is not used by any other thread.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_swap_puzzle(board: *mut Board, other: *mut Board) -> bool {
+    if board.is_null() || other.is_null() || board == other {
+        return false;
     }
+    let board = board.as_mut().unwrap();
+
+    board.swap_puzzle(*Box::from_raw(other));
+    true
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_get_number(board: *mut Board, row: u32, column: u32) -> u8 {
+pub unsafe extern "C" fn sudoku_get_number(board: *mut Board, row: u32, column: u32) -> u8 {
     let board = unsafe { board.as_ref().unwrap() };
+    if !contains(board, row, column) {
+        return 0;
+    }
 
     board.cell(row as usize, column as usize).selected().unwrap_or(0)
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_size(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.size() as u32
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_box_rows(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.box_shape().0 as u32
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_box_columns(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.box_shape().1 as u32
+}
+
+/// difficulty 为 Difficulty 的值, 无效时返回 false
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_set_difficulty(board: *mut Board, difficulty: u32) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    match Difficulty::from_u32(difficulty) {
+        Some(difficulty) => {
+            board.set_difficulty(difficulty);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 生成失败时返回 false, 盘面保持不变
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_generate(board: *mut Board) {
+pub unsafe extern "C" fn sudoku_generate(board: *mut Board) -> bool {
     let board = unsafe { board.as_mut().unwrap() };
 
-    board.generate();
+    board.generate()
+}
+
+/// 生成极小题目, 返回提示数的个数. 杀手数独和大于 9x9 的盘面不支持, 返回 0
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate_minimal(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.generate_minimal().map_or(0, |clues| clues as u32)
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_clue_count(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.clue_count() as u32
 }
 
+/// 去掉任意一个提示数都会多解, 杀手数独总是返回 false
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_dump(board: *mut Board) {
+pub unsafe extern "C" fn sudoku_is_minimal(board: *mut Board) -> bool {
     let board = unsafe { board.as_ref().unwrap() };
 
-    println!("{}", board);
+    board.is_minimal()
+}
+
+/// 在工作线程上生成题目, 不阻塞调用者. on_done 在工作线程上调用,
+/// 之后回到使用 board 的线程调用 sudoku_job_finish 取得题目
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate_start(board: *mut Board, ptr: *mut c_void, on_done: Option<JobCallback>) -> *mut GenerateJob {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    Box::into_raw(Box::new(GenerateJob::start_with_callback(board, ptr, on_done)))
+}
+
+/// 0.0 到 1.0
+///
+/// # Safety
+///
+/// `job` must be a pointer returned by `sudoku_generate_start` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_job_progress(job: *mut GenerateJob) -> f32 {
+    let job = unsafe { job.as_ref().unwrap() };
+
+    job.progress()
+}
+
+/// # Safety
+///
+/// `job` must be a pointer returned by `sudoku_generate_start` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_job_cancel(job: *mut GenerateJob) {
+    let job = unsafe { job.as_ref().unwrap() };
+
+    job.cancel();
 }
 
+/// # Safety
+///
+/// `job` must be a pointer returned by `sudoku_generate_start` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_set_update_callback(board: *mut Board, ptr: *mut c_void, cb: extern fn(*mut c_void, u32, u32)) {
+pub unsafe extern "C" fn sudoku_job_is_finished(job: *mut GenerateJob) -> bool {
+    let job = unsafe { job.as_ref().unwrap() };
+
+    job.is_finished()
+}
+
+/// 把生成的题目放到 board 上, 未完成或已取消时返回 false
+///
+/// # Safety
+///
+/// `job` must be a pointer returned by `sudoku_generate_start` that has not been freed yet.
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_job_finish(job: *mut GenerateJob, board: *mut Board) -> bool {
+    let job = unsafe { job.as_ref().unwrap() };
+    let board = unsafe { board.as_mut().unwrap() };
+
+    match job.take() {
+        Some(generated) => {
+            board.swap_puzzle(generated);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 未完成时会先取消并等待工作线程退出.
+///
+/// # Safety
+///
+/// `job` must be null or a pointer returned by `sudoku_generate_start` that has not been freed yet.
+/// It must not be freed from inside its own completion callback.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_job_free(job: *mut GenerateJob) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
+
+// 全局题目池, 由 sudoku_pool_init 创建
+static POOL: Mutex<Option<PuzzlePool>> = Mutex::new(None);
+
+/// 设置题目池, path 为 NULL 时不写文件. 文件无法读取时返回 false
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_pool_init(path: *const c_char, capacity: u32) -> bool {
+    let pool = if path.is_null() {
+        PuzzlePool::in_memory(capacity as usize)
+    } else {
+        let path = unsafe { CStr::from_ptr(path) };
+        match path.to_str().map(|p| PuzzlePool::open(p, capacity as usize)) {
+            Ok(Ok(pool)) => pool,
+            _ => return false,
+        }
+    };
+
+    // 旧的池在锁外释放, 避免等待其后台线程时阻塞其他调用
+    let old = POOL.lock().unwrap().replace(pool);
+    drop(old);
+
+    true
+}
+
+// 停止后台补充, 已持久化的题目下次 sudoku_pool_init 时恢复
+#[no_mangle]
+pub extern "C" fn sudoku_pool_shutdown() {
+    let old = POOL.lock().unwrap().take();
+    drop(old);
+}
+
+// 难度无效或未初始化时返回 0
+#[no_mangle]
+pub extern "C" fn sudoku_pool_count(difficulty: u32) -> u32 {
+    let difficulty = match Difficulty::from_u32(difficulty) {
+        Some(difficulty) => difficulty,
+        None => return 0,
+    };
+
+    POOL.lock().unwrap().as_ref().map_or(0, |pool| pool.count(difficulty) as u32)
+}
+
+// 从题目池取出一道标准题目, 返回的盘面需要 sudoku_free. 难度无效或生成失败时返回 NULL.
+// 池为空或未初始化时当场生成, 生成期间不持有池的锁
+#[no_mangle]
+pub extern "C" fn sudoku_take_puzzle(difficulty: u32) -> *mut Board {
+    let difficulty = match Difficulty::from_u32(difficulty) {
+        Some(difficulty) => difficulty,
+        None => return null_mut(),
+    };
+
+    let taken = POOL.lock().unwrap().as_ref().and_then(|pool| pool.try_take(difficulty));
+    let board = taken.or_else(|| {
+        let mut board = Board::empty();
+        board.set_difficulty(difficulty);
+        board.generate().then_some(board)
+    });
+
+    board.map_or(null_mut(), |board| Box::into_raw(Box::new(board)))
+}
+
+/// 生成提示数在 [min_clues, max_clues] 内的题目, 成功返回 0, 否则返回 GenerateError
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate_with_clues(board: *mut Board, min_clues: u32, max_clues: u32, timeout_ms: u32) -> u32 {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    match board.generate_with_clues(min_clues as usize, max_clues as usize, Duration::from_millis(timeout_ms as u64)) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// 生成需要特定技巧的题目, 参数与 TechniqueGoal 的字段对应, 技巧为 Technique 的值.
+/// 成功返回 0, 否则返回 GenerateError
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate_training(board: *mut Board, required: u32, ceiling: u32, min_uses: u32, max_uses: u32, timeout_ms: u32) -> u32 {
+    let board = unsafe { board.as_mut().unwrap() };
+    let goal = match (Technique::from_u32(required), Technique::from_u32(ceiling)) {
+        (Some(required), Some(ceiling)) => TechniqueGoal { required, ceiling, min_uses, max_uses },
+        _ => return GenerateError::InvalidGoal as u32,
+    };
+
+    match board.generate_training(goal, Duration::from_millis(timeout_ms as u64)) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+/// 通过日志输出盘面
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_dump(board: *mut Board) {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    info!("{}", board);
+}
+
+// 接收不高于 max_level 的日志, cb 为 NULL 时取消. max_level 无效时返回 false
+#[no_mangle]
+pub extern "C" fn sudoku_set_log_callback(ptr: *mut c_void, cb: Option<LogCallback>, max_level: u32) -> bool {
+    match Level::from_u32(max_level) {
+        Some(max_level) => {
+            logging::set_callback(cb, ptr, max_level);
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_set_update_callback(board: *mut Board, ptr: *mut c_void, cb: extern "C" fn(*mut c_void, u32, u32)) {
     let board = unsafe { board.as_mut().unwrap() };
 
     board.set_callback_ptr(ptr);
     board.set_update_callback(cb);
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_get_candidate(board: *mut Board, row: u32, column: u32) -> u32 {
+pub unsafe extern "C" fn sudoku_get_candidate(board: *mut Board, row: u32, column: u32) -> u32 {
     let board = unsafe { board.as_ref().unwrap() };
+    if !contains(board, row, column) {
+        return 0;
+    }
 
-    board.cell(row as usize, column as usize).candidate_u32()
+    board.candidates(row as usize, column as usize)
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_get_cell_state(board: *mut Board, row: u32, column: u32) -> CellStates {
+pub unsafe extern "C" fn sudoku_get_cell_state(board: *mut Board, row: u32, column: u32) -> CellStates {
     let board = unsafe { board.as_ref().unwrap() };
+    if !contains(board, row, column) {
+        return CellStates::NONE;
+    }
 
     board.cell(row as usize, column as usize).states()
 }
 
+/// 返回本次输入是否算作错误, val 不在 1..=size 内时返回 false
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_set_cell(board: *mut Board, row: u32, column: u32, val: u8) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+    if !contains(board, row, column) || val == 0 || val as usize > board.size() {
+        return false;
+    }
+
+    board.enter(row as usize, column as usize, Some(val))
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_set_cell(board: *mut Board, row: u32, column: u32, val: u8) {
+pub unsafe extern "C" fn sudoku_clear_cell(board: *mut Board, row: u32, column: u32) {
     let board = unsafe { board.as_mut().unwrap() };
+    if !contains(board, row, column) {
+        return;
+    }
+
+    board.enter(row as usize, column as usize, None);
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_toggle_note(board: *mut Board, row: u32, column: u32, val: u8) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+    if !contains(board, row, column) {
+        return false;
+    }
+
+    board.toggle_note(row as usize, column as usize, val)
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_notes(board: *mut Board, row: u32, column: u32) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+    if !contains(board, row, column) {
+        return 0;
+    }
+
+    board.cell(row as usize, column as usize).notes()
+}
+
+/// 填入一个答案, 没有可提示的格子时返回 false
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_hint(board: *mut Board) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.hint().is_some()
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_undo(board: *mut Board) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.undo()
+}
+
+// 以下三个函数返回改变的格子数, 整体作为一次撤销
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_autofill_notes(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.autofill_notes() as u32
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_apply_naked_singles(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.apply_naked_singles() as u32
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_apply_hidden_singles(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.apply_hidden_singles() as u32
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_is_solved(board: *mut Board) -> bool {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.is_solved()
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_stats(board: *mut Board) -> GameStats {
+    let board = unsafe { board.as_ref().unwrap() };
 
-    board.set(row as usize, column as usize, Some(val));
+    board.stats()
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_clear_cell(board: *mut Board, row: u32, column: u32) {
+pub unsafe extern "C" fn sudoku_set_score_config(board: *mut Board, config: ScoreConfig) {
     let board = unsafe { board.as_mut().unwrap() };
 
-    board.set(row as usize, column as usize, None);
+    board.set_score_config(config);
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_set_highlight(board: *mut Board, highlight: u8) {
+pub unsafe extern "C" fn sudoku_set_highlight(board: *mut Board, highlight: u8) {
     let board = unsafe { board.as_mut().unwrap() };
 
     board.set_current_highlight(Some(highlight));
 }
 
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
 #[no_mangle]
-pub extern fn sudoku_clear_highlight(board: *mut Board) {
+pub unsafe extern "C" fn sudoku_clear_highlight(board: *mut Board) {
     let board = unsafe { board.as_mut().unwrap() };
 
     board.set_current_highlight(None);
 }
+
+/// mode 为 HighlightMode 的值, 无效时返回 false. digit 为 0 表示不指定数字
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_set_highlight_mode(board: *mut Board, mode: u32, digit: u8) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    match HighlightMode::from_u32(mode) {
+        Some(mode) => {
+            board.set_highlight(mode, Some(digit).filter(|&d| d != 0));
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_highlight_mode(board: *mut Board) -> HighlightMode {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.highlight_mode()
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_select_cell(board: *mut Board, row: u32, column: u32) {
+    let board = unsafe { board.as_mut().unwrap() };
+    if !contains(board, row, column) {
+        return;
+    }
+
+    board.select(Some((row as usize, column as usize)));
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_clear_selection(board: *mut Board) {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.select(None);
+}
+
+/// 没有选中的格子或 row, column 为 NULL 时返回 false
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+/// `row` must be null or valid for writes.
+/// `column` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_selection(board: *mut Board, row: *mut u32, column: *mut u32) -> bool {
+    let board = unsafe { board.as_ref().unwrap() };
+    if row.is_null() || column.is_null() {
+        return false;
+    }
+
+    match board.selected_cell() {
+        Some((r, c)) => {
+            unsafe {
+                *row = r as u32;
+                *column = c as u32;
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// 格子所在的区域, 超出盘面时返回 u32::MAX
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_region(board: *mut Board, row: u32, column: u32) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+    if !contains(board, row, column) {
+        return u32::MAX;
+    }
+
+    board.region(row as usize, column as usize) as u32
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_cage_count(board: *mut Board) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.cages().len() as u32
+}
+
+/// 笼子不存在时返回 0
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_cage_sum(board: *mut Board, cage: u32) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.cages().get(cage as usize).map_or(0, |c| c.sum())
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_cage_size(board: *mut Board, cage: u32) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.cages().get(cage as usize).map_or(0, |c| c.cells().len() as u32)
+}
+
+/// 笼子中第 n 个格子, 返回 row * size + column, 超出范围时返回 u32::MAX
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_cage_cell(board: *mut Board, cage: u32, n: u32) -> u32 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.cages().get(cage as usize)
+        .and_then(|c| c.cells().get(n as usize))
+        .map_or(u32::MAX, |&cell| cell as u32)
+}
+
+/// 格子所在的笼子, 不在笼子中或超出盘面时返回 -1
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_get_cell_cage(board: *mut Board, row: u32, column: u32) -> i32 {
+    let board = unsafe { board.as_ref().unwrap() };
+    if !contains(board, row, column) {
+        return -1;
+    }
+
+    board.cage_of(row as usize, column as usize).map_or(-1, |x| x as i32)
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+/// `puzzle` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_load(board: *mut Board, puzzle: *const c_char) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+    if puzzle.is_null() {
+        return false;
+    }
+    let puzzle = unsafe { CStr::from_ptr(puzzle) };
+
+    match puzzle.to_str() {
+        Ok(puzzle) => board.load(puzzle),
+        Err(_) => false,
+    }
+}
+
+/// 返回的字符串需要用 sudoku_free_string 释放
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_export(board: *mut Board) -> *mut c_char {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    CString::new(board.export()).unwrap().into_raw()
+}
+
+/// 对盘面做一步变换, kind 为 TransformKind 的值, a 和 b 只用于交换.
+/// kind 无效或变换对当前变体无效时返回 false
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_transform(board: *mut Board, kind: u32, a: u32, b: u32) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+    let kind = match TransformKind::from_u32(kind) {
+        Some(kind) => kind,
+        None => return false,
+    };
+    let (box_rows, box_columns) = board.box_shape();
+
+    let mut transform = Transform::identity(box_rows, box_columns);
+    transform.apply_kind(kind, a as usize, b as usize) && board.transform(&transform)
+}
+
+/// 随机的等价变换, 得到看起来不同的同一道题目
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_transform_random(board: *mut Board) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+    let (box_rows, box_columns) = board.box_shape();
+
+    board.transform(&Transform::random(&mut rand::thread_rng(), box_rows, box_columns))
+}
+
+/// 替换数字, digits[v - 1] 为数字 v 的新值. digits 为 NULL 时返回 false
+///
+/// # Safety
+///
+/// digits 必须为 NULL 或指向长度为盘面边长的数组
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_permute_digits(board: *mut Board, digits: *const u8) -> bool {
+    let board = unsafe { board.as_mut().unwrap() };
+    if digits.is_null() {
+        return false;
+    }
+    let (box_rows, box_columns) = board.box_shape();
+    let digits = unsafe { std::slice::from_raw_parts(digits, board.size()) };
+
+    let mut transform = Transform::identity(box_rows, box_columns);
+    transform.permute_digits(digits) && board.transform(&transform)
+}
+
+/// 题目的规范形式, 不支持的变体和尺寸返回 NULL. 返回的字符串需要用 sudoku_free_string 释放
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_canonical(board: *mut Board) -> *mut c_char {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    match board.canonical() {
+        Some(canonical) => CString::new(canonical).unwrap().into_raw(),
+        None => null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_pause(board: *mut Board) {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.pause();
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_resume(board: *mut Board) {
+    let board = unsafe { board.as_mut().unwrap() };
+
+    board.resume();
+}
+
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_elapsed_ms(board: *mut Board) -> u64 {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    board.elapsed_ms()
+}
+
+/// 返回的字符串需要用 sudoku_free_string 释放
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_save(board: *mut Board) -> *mut c_char {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    CString::new(board.save()).unwrap().into_raw()
+}
+
+/// 存档为 NULL 或无效时返回 NULL
+///
+/// # Safety
+///
+/// `data` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_restore(data: *const c_char) -> *mut Board {
+    if data.is_null() {
+        return null_mut();
+    }
+    let data = unsafe { CStr::from_ptr(data) };
+
+    match data.to_str().ok().and_then(Board::from_save) {
+        Some(board) => Box::into_raw(Box::new(board)),
+        None => null_mut(),
+    }
+}
+
+/// 打开历史记录文件, path 为 NULL、文件损坏或无法读取时返回 NULL
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_history_open(path: *const c_char) -> *mut History {
+    if path.is_null() {
+        return null_mut();
+    }
+    let path = unsafe { CStr::from_ptr(path) };
+
+    match path.to_str().ok().map(History::open) {
+        Some(Ok(history)) => Box::into_raw(Box::new(history)),
+        _ => null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `history` must be null or a pointer returned by `sudoku_history_open` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_history_free(history: *mut History) {
+    if !history.is_null() {
+        drop(Box::from_raw(history));
+    }
+}
+
+/// 记录 board 当前这一局, 写文件失败时返回 false
+///
+/// # Safety
+///
+/// `history` must be a pointer returned by `sudoku_history_open` that has not been freed yet.
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_history_record(history: *mut History, board: *mut Board, won: bool) -> bool {
+    let history = unsafe { history.as_mut().unwrap() };
+    let board = unsafe { board.as_ref().unwrap() };
+
+    history.record(GameRecord::from_board(board, won)).is_ok()
+}
+
+/// 所有记录的统计, 用时混合了不同的尺寸和变体
+///
+/// # Safety
+///
+/// `history` must be a pointer returned by `sudoku_history_open` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_history_stats(history: *mut History) -> PlayerStats {
+    let history = unsafe { history.as_ref().unwrap() };
+
+    history.stats(StatsFilter::default())
+}
+
+/// 某一种题目的统计, difficulty 和 variant 为 Difficulty 和 Variant 的值, size 为边长.
+/// 无效的 difficulty 或 variant 返回全为 0 的统计
+///
+/// # Safety
+///
+/// `history` must be a pointer returned by `sudoku_history_open` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_history_stats_for(history: *mut History, difficulty: u32, variant: u32, size: u32) -> PlayerStats {
+    let history = unsafe { history.as_ref().unwrap() };
+
+    match (Difficulty::from_u32(difficulty), Variant::from_u32(variant)) {
+        (Some(difficulty), Some(variant)) => history.stats(StatsFilter::game(difficulty, variant, size as usize)),
+        _ => PlayerStats::default(),
+    }
+}
+
+/// 人工解题过程的 JSON, 需要用 sudoku_free_string 释放
+///
+/// # Safety
+///
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_solve_logically(board: *mut Board) -> *mut c_char {
+    let board = unsafe { board.as_ref().unwrap() };
+
+    CString::new(board.solve_logically().to_json()).unwrap().into_raw()
+}
+
+/// 从 sudoku_solve_logically 的结果创建回放, 数据为 NULL 或无效时返回 NULL.
+/// 与盘面尺寸不符的步骤在 sudoku_replay_next 中被拒绝
+///
+/// # Safety
+///
+/// `trace` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_replay_new(trace: *const c_char) -> *mut Replay {
+    if trace.is_null() {
+        return null_mut();
+    }
+    let trace = unsafe { CStr::from_ptr(trace) };
+
+    match trace.to_str().ok().and_then(SolveTrace::from_json) {
+        Some(trace) => Box::into_raw(Box::new(Replay::new(trace))),
+        None => null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `replay` must be null or a pointer returned by `sudoku_replay_new` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_replay_free(replay: *mut Replay) {
+    if !replay.is_null() {
+        drop(Box::from_raw(replay));
+    }
+}
+
+/// 应用下一步并触发更新回调, 已经结束或步骤超出盘面范围时返回 false
+///
+/// # Safety
+///
+/// `replay` must be a pointer returned by `sudoku_replay_new` that has not been freed yet.
+/// `board` must be a pointer returned by `sudoku_new*` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_replay_next(replay: *mut Replay, board: *mut Board) -> bool {
+    let replay = unsafe { replay.as_mut().unwrap() };
+    let board = unsafe { board.as_mut().unwrap() };
+
+    replay.next(board).is_some()
+}
@@ -0,0 +1,134 @@
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_char, c_void};
+use std::sync::RwLock;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    // C 接口传入的值, 无效时返回 None
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            1 => Some(Level::Error),
+            2 => Some(Level::Warn),
+            3 => Some(Level::Info),
+            4 => Some(Level::Debug),
+            5 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+pub type LogCallback = extern "C" fn(*mut c_void, Level, *const c_char);
+
+#[derive(Clone, Copy)]
+struct Sink {
+    callback: LogCallback,
+    // 宿主传入的指针, 只原样传回
+    ptr: usize,
+    max_level: Level,
+}
+
+static SINK: RwLock<Option<Sink>> = RwLock::new(None);
+
+// 设置接收日志的回调, 只转发不高于 max_level 的消息
+pub fn set_callback(callback: Option<LogCallback>, ptr: *mut c_void, max_level: Level) {
+    let sink = callback.map(|callback| Sink { callback, ptr: ptr as usize, max_level });
+
+    *SINK.write().unwrap() = sink;
+}
+
+#[cfg(feature = "log")]
+fn to_log_level(level: Level) -> log::Level {
+    match level {
+        Level::Error => log::Level::Error,
+        Level::Warn => log::Level::Warn,
+        Level::Info => log::Level::Info,
+        Level::Debug => log::Level::Debug,
+        Level::Trace => log::Level::Trace,
+    }
+}
+
+// 没有人接收时不会格式化消息
+pub fn log(level: Level, args: fmt::Arguments<'_>) {
+    #[cfg(feature = "log")]
+    log::log!(target: "sudoku", to_log_level(level), "{}", args);
+
+    // 回调可能重新设置回调或再次输出日志, 调用前先释放锁
+    let sink = *SINK.read().unwrap();
+    if let Some(sink) = sink.filter(|s| level <= s.max_level) {
+        let message = CString::new(args.to_string().replace('\0', "")).unwrap();
+        (sink.callback)(sink.ptr as *mut c_void, level, message.as_ptr());
+    }
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Trace, format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+    use std::ptr::null_mut;
+    use std::sync::Mutex;
+    use crate::logging::*;
+
+    static RECEIVED: Mutex<Vec<(Level, String)>> = Mutex::new(vec![]);
+
+    extern "C" fn collect(_: *mut c_void, level: Level, message: *const c_char) {
+        let message = unsafe { CStr::from_ptr(message) }.to_str().unwrap().to_string();
+        RECEIVED.lock().unwrap().push((level, message));
+    }
+
+    // 收到日志后取消回调
+    extern "C" fn unset(_: *mut c_void, level: Level, message: *const c_char) {
+        set_callback(None, null_mut(), Level::Trace);
+        collect(null_mut(), level, message);
+    }
+
+    #[test]
+    fn test_callback() {
+        set_callback(Some(collect), null_mut(), Level::Debug);
+        debug!("removed {} cells", 50);
+        trace!("hidden");
+        info!("shown");
+        set_callback(None, null_mut(), Level::Trace);
+        debug!("after");
+
+        let received = RECEIVED.lock().unwrap();
+        let ours: Vec<_> = received.iter().filter(|(_, m)| m != "after").cloned().collect();
+        assert!(ours.contains(&(Level::Debug, "removed 50 cells".to_string())));
+        assert!(ours.contains(&(Level::Info, "shown".to_string())));
+        assert!(!ours.iter().any(|(_, m)| m == "hidden"));
+        assert!(!received.iter().any(|(_, m)| m == "after"));
+        drop(received);
+
+        // 在回调中修改回调不会死锁
+        set_callback(Some(unset), null_mut(), Level::Info);
+        info!("reentrant");
+        assert!(RECEIVED.lock().unwrap().iter().any(|(_, m)| m == "reentrant"));
+        assert!(SINK.read().unwrap().is_none());
+    }
+}
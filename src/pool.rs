@@ -0,0 +1,250 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::difficulty::Difficulty;
+use crate::job::Progress;
+
+// 池中的一道标准 9x9 题目
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct PoolEntry {
+    difficulty: Difficulty,
+    puzzle: String,
+}
+
+struct PoolState {
+    entries: Vec<PoolEntry>,
+    // entries 有改动还没写入文件
+    dirty: bool,
+    stopped: bool,
+}
+
+impl PoolState {
+    fn count(&self, difficulty: Difficulty) -> usize {
+        self.entries.iter().filter(|e| e.difficulty == difficulty).count()
+    }
+}
+
+struct Shared {
+    path: Option<PathBuf>,
+    capacity: usize,
+    state: Mutex<PoolState>,
+    // 有题目被取走或需要停止时唤醒后台线程, 文件也由后台线程写入
+    wake: Condvar,
+    // 停止时取消正在进行的生成
+    progress: Progress,
+}
+
+impl Shared {
+    // 与 History 相同, 先写临时文件再改名
+    fn save(&self, entries: &[PoolEntry]) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let tmp = path.with_extension("tmp");
+        let result = serde_json::to_string(entries).map_err(io::Error::from)
+            .and_then(|data| fs::write(&tmp, data))
+            .and_then(|_| fs::rename(&tmp, path));
+        if let Err(e) = result {
+            debug!("Failed to save puzzle pool: {}", e);
+        }
+    }
+}
+
+// 每个难度预先生成 capacity 道题目, 由后台线程补充
+pub struct PuzzlePool {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl PuzzlePool {
+    // 不写文件
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::start(None, capacity, vec![])
+    }
+
+    // 从文件恢复上次剩余的题目, 文件不存在时为空
+    pub fn open<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self::start(Some(path), capacity, entries))
+    }
+
+    fn start(path: Option<PathBuf>, capacity: usize, entries: Vec<PoolEntry>) -> Self {
+        let shared = Arc::new(Shared {
+            path,
+            capacity,
+            state: Mutex::new(PoolState { entries, dirty: false, stopped: false }),
+            wake: Condvar::new(),
+            progress: Progress::new(),
+        });
+
+        let worker = {
+            let shared = shared.clone();
+            thread::spawn(move || refill(&shared))
+        };
+
+        Self { shared, worker: Some(worker) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    // 池中现有的题目数
+    pub fn count(&self, difficulty: Difficulty) -> usize {
+        self.shared.state.lock().unwrap().count(difficulty)
+    }
+
+    // 取出一道题目开始新的一局, 池为空时当场生成. 取走后后台自动补充
+    pub fn take(&self, difficulty: Difficulty) -> Board {
+        self.try_take(difficulty).unwrap_or_else(|| {
+            let mut board = Board::empty();
+            board.set_difficulty(difficulty);
+            board.generate();
+            board
+        })
+    }
+
+    // 只从池中取, 池为空时返回 None, 不会生成
+    pub fn try_take(&self, difficulty: Difficulty) -> Option<Board> {
+        let entry = {
+            let mut state = self.shared.state.lock().unwrap();
+            let entry = state.entries.iter().position(|e| e.difficulty == difficulty)
+                .map(|i| state.entries.remove(i));
+            state.dirty |= entry.is_some();
+            entry
+        };
+        self.shared.wake.notify_one();
+
+        let mut board = Board::empty();
+        board.set_difficulty(difficulty);
+        entry.filter(|entry| board.load(&entry.puzzle)).map(|_| board)
+    }
+}
+
+// 停止后台线程, 正在生成的题目会被放弃
+impl Drop for PuzzlePool {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.progress.cancel();
+        self.shared.wake.notify_one();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+// 后台线程: 每次为题目最少的难度生成一道, 全部满了就等待.
+// 写文件在锁外进行, 不阻塞取题目的线程
+fn refill(shared: &Shared) {
+    loop {
+        let difficulty = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if state.dirty {
+                    state.dirty = false;
+                    let entries = state.entries.clone();
+                    drop(state);
+                    shared.save(&entries);
+                    state = shared.state.lock().unwrap();
+                    continue;
+                }
+                if state.stopped {
+                    return;
+                }
+
+                let lacking = Difficulty::ALL.iter().copied()
+                    .filter(|&d| state.count(d) < shared.capacity)
+                    .min_by_key(|&d| state.count(d));
+                match lacking {
+                    Some(difficulty) => break difficulty,
+                    None => state = shared.wake.wait(state).unwrap(),
+                }
+            }
+        };
+
+        let mut board = Board::empty();
+        board.set_difficulty(difficulty);
+        // 停止时取消, 回到开头把未保存的改动写完再退出
+        if !board.generate_with_progress(&shared.progress) {
+            continue;
+        }
+
+        let mut state = shared.state.lock().unwrap();
+        state.entries.push(PoolEntry { difficulty, puzzle: board.export() });
+        state.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::pool::*;
+
+    // 等待后台线程补满
+    fn wait_full(pool: &PuzzlePool) {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while Difficulty::ALL.iter().any(|&d| pool.count(d) < pool.capacity()) {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_take() {
+        let pool = PuzzlePool::in_memory(2);
+        wait_full(&pool);
+
+        let board = pool.take(Difficulty::Hard);
+        assert_eq!(Difficulty::Hard, board.difficulty());
+        assert!(board.has_unique_solution());
+        assert!(board.values().iter().filter(|&&v| v != 0).count() >= 81 - 56);
+
+        wait_full(&pool);
+        assert_eq!(2, pool.count(Difficulty::Hard));
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        let pool = PuzzlePool::in_memory(0);
+        assert!(pool.try_take(Difficulty::Easy).is_none());
+        let board = pool.take(Difficulty::Easy);
+        assert!(board.has_unique_solution());
+        assert_eq!(0, pool.count(Difficulty::Easy));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = std::env::temp_dir().join(format!("sudoku-pool-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let pool = PuzzlePool::open(&path, 1).unwrap();
+        wait_full(&pool);
+        drop(pool);
+
+        // 重新打开后不需要生成就有题目
+        let pool = PuzzlePool::open(&path, 1).unwrap();
+        assert_eq!(1, pool.count(Difficulty::Expert));
+        let puzzle = pool.take(Difficulty::Expert).export();
+        drop(pool);
+
+        let data = fs::read_to_string(&path).unwrap();
+        assert!(!data.contains(&puzzle));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(PuzzlePool::open(&path, 1).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
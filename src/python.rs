@@ -0,0 +1,147 @@
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::board::Board;
+use crate::cell::digit_to_char;
+use crate::difficulty::Difficulty;
+use crate::variant::Variant;
+
+// Python 中的 sudoku.Board, 用于批量分析和筛选题目
+#[pyclass(name = "Board", unsendable)]
+pub struct PyBoard {
+    board: Board,
+}
+
+fn parse_difficulty(name: &str) -> PyResult<Difficulty> {
+    match name.to_ascii_lowercase().as_str() {
+        "easy" => Ok(Difficulty::Easy),
+        "medium" => Ok(Difficulty::Medium),
+        "hard" => Ok(Difficulty::Hard),
+        "expert" => Ok(Difficulty::Expert),
+        _ => Err(PyValueError::new_err(format!("unknown difficulty: {}", name))),
+    }
+}
+
+fn parse_variant(name: &str) -> PyResult<Variant> {
+    match name.to_ascii_lowercase().as_str() {
+        "standard" => Ok(Variant::Standard),
+        "diagonal" => Ok(Variant::Diagonal),
+        "jigsaw" => Ok(Variant::Jigsaw),
+        "killer" => Ok(Variant::Killer),
+        _ => Err(PyValueError::new_err(format!("unknown variant: {}", name))),
+    }
+}
+
+#[pymethods]
+impl PyBoard {
+    #[new]
+    #[pyo3(signature = (size = 9, variant = "standard"))]
+    fn new(size: usize, variant: &str) -> PyResult<Self> {
+        Board::with_size(size, parse_variant(variant)?)
+            .map(|board| Self { board })
+            .ok_or_else(|| PyValueError::new_err(format!("unsupported size: {}", size)))
+    }
+
+    // 题目字符串, 空格用 '.' 或 '0' 表示
+    #[staticmethod]
+    #[pyo3(signature = (puzzle, size = 9, variant = "standard"))]
+    fn load(puzzle: &str, size: usize, variant: &str) -> PyResult<Self> {
+        let mut board = Self::new(size, variant)?;
+        if !board.board.load(puzzle) {
+            return Err(PyValueError::new_err("invalid puzzle"));
+        }
+
+        Ok(board)
+    }
+
+    // 指定 seed 时生成可重现的标准 9x9 题目
+    #[staticmethod]
+    #[pyo3(signature = (difficulty = "medium", seed = None, size = 9, variant = "standard"))]
+    fn generate(difficulty: &str, seed: Option<u64>, size: usize, variant: &str) -> PyResult<Self> {
+        let difficulty = parse_difficulty(difficulty)?;
+        if let Some(seed) = seed {
+            if size != 9 || parse_variant(variant)? != Variant::Standard {
+                return Err(PyValueError::new_err("seeded generation only supports standard 9x9"));
+            }
+            return Ok(Self { board: Board::seeded(seed, difficulty) });
+        }
+
+        let mut board = Self::new(size, variant)?;
+        board.board.set_difficulty(difficulty);
+        if !board.board.generate() {
+            return Err(PyRuntimeError::new_err("failed to generate a puzzle"));
+        }
+
+        Ok(board)
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.board.size()
+    }
+
+    #[getter]
+    fn seed(&self) -> Option<u64> {
+        self.board.seed()
+    }
+
+    fn export(&self) -> String {
+        self.board.export()
+    }
+
+    fn values(&self) -> Vec<u8> {
+        self.board.values()
+    }
+
+    // 无解时返回 None
+    fn solve(&self) -> Option<String> {
+        self.board.solve().map(|values| values.into_iter().map(digit_to_char).collect())
+    }
+
+    #[pyo3(signature = (limit = 2))]
+    fn count_solutions(&self, limit: usize) -> usize {
+        self.board.count_solutions(limit)
+    }
+
+    fn has_unique_solution(&self) -> bool {
+        self.board.has_unique_solution()
+    }
+
+    // 人工解题的评级: 是否能解完、最难的技巧和步数
+    fn grade<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let trace = self.board.solve_logically();
+        let result = PyDict::new(py);
+        result.set_item("solved", trace.solved)?;
+        result.set_item("hardest", trace.hardest().map(|t| t.name()))?;
+        result.set_item("techniques", trace.steps.iter().map(|s| s.technique.name()).collect::<Vec<_>>())?;
+        result.set_item("steps", trace.steps.len())?;
+
+        Ok(result)
+    }
+
+    fn clue_count(&self) -> usize {
+        self.board.clue_count()
+    }
+
+    // 去掉任意一个提示数都会多解
+    fn is_minimal(&self) -> bool {
+        self.board.is_minimal()
+    }
+
+    // 等价变换下的规范形式, 用于去重; 不支持的变体和尺寸返回 None
+    fn canonical(&self) -> Option<String> {
+        self.board.canonical()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Board('{}')", self.board.export())
+    }
+}
+
+#[pymodule(name = "sudoku")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBoard>()?;
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use rand::Rng;
+
+// 标准宫的区域编号
+pub fn box_regions(size: usize, box_rows: usize, box_columns: usize) -> Vec<u8> {
+    let stacks = size / box_columns;
+
+    (0..size * size)
+        .map(|i| ((i / size / box_rows) * stacks + (i % size) / box_columns) as u8)
+        .collect()
+}
+
+fn neighbours(cell: usize, size: usize) -> impl Iterator<Item = usize> {
+    let row = cell / size;
+    let column = cell % size;
+
+    vec![
+        (row > 0).then(|| cell - size),
+        (row + 1 < size).then(|| cell + size),
+        (column > 0).then(|| cell - 1),
+        (column + 1 < size).then(|| cell + 1),
+    ].into_iter().flatten()
+}
+
+fn is_connected(regions: &[u8], size: usize, region: u8) -> bool {
+    let cells: Vec<usize> = (0..regions.len()).filter(|&i| regions[i] == region).collect();
+    if cells.is_empty() {
+        return false;
+    }
+
+    let mut visited = vec![false; regions.len()];
+    let mut stack = vec![cells[0]];
+    let mut count = 0;
+    visited[cells[0]] = true;
+    while let Some(cell) = stack.pop() {
+        count += 1;
+        for n in neighbours(cell, size) {
+            if !visited[n] && regions[n] == region {
+                visited[n] = true;
+                stack.push(n);
+            }
+        }
+    }
+
+    count == cells.len()
+}
+
+// size 个区域, 每个区域 size 个相连的格子
+pub fn is_valid_layout(regions: &[u8], size: usize) -> bool {
+    if regions.len() != size * size {
+        return false;
+    }
+
+    (0..size as u8).all(|r| {
+        regions.iter().filter(|&&x| x == r).count() == size && is_connected(regions, size, r)
+    })
+}
+
+// 从标准宫开始, 反复交换相邻区域边界上的两个格子并保持区域连通.
+// 给出 solution 时只交换数字相同的格子, 对标准宫成立的解对新的区域仍然成立
+pub fn random_layout<R: Rng>(rng: &mut R, size: usize, box_rows: usize, box_columns: usize, solution: Option<&[u8]>) -> Vec<u8> {
+    let cell_count = size * size;
+    let mut regions = box_regions(size, box_rows, box_columns);
+
+    for _ in 0..cell_count * 4 {
+        let a = rng.gen_range(0, cell_count);
+        let ra = regions[a];
+        let borders: Vec<usize> = neighbours(a, size).filter(|&n| regions[n] != ra).collect();
+        if borders.is_empty() {
+            continue;
+        }
+
+        let rb = regions[borders[rng.gen_range(0, borders.len())]];
+        // b 属于 rb 且和 ra 相邻
+        let candidates: Vec<usize> = (0..cell_count)
+            .filter(|&b| regions[b] == rb && b != a && solution.is_none_or(|s| s[b] == s[a]))
+            .filter(|&b| neighbours(b, size).any(|n| n != a && regions[n] == ra))
+            .collect();
+        if candidates.is_empty() {
+            continue;
+        }
+
+        let b = candidates[rng.gen_range(0, candidates.len())];
+        regions[a] = rb;
+        regions[b] = ra;
+        if !is_connected(&regions, size, ra) || !is_connected(&regions, size, rb) {
+            regions[a] = ra;
+            regions[b] = rb;
+        }
+    }
+
+    debug_assert!(is_valid_layout(&regions, size));
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::region::*;
+
+    #[test]
+    fn test_box_regions() {
+        let regions = box_regions(9, 3, 3);
+        assert!(is_valid_layout(&regions, 9));
+        assert_eq!(0, regions[20]);
+        assert_eq!(4, regions[40]);
+        assert_eq!(8, regions[80]);
+
+        // 6x6, 宫为 2 行 3 列
+        let regions = box_regions(6, 2, 3);
+        assert!(is_valid_layout(&regions, 6));
+        assert_eq!(1, regions[3]);
+        assert_eq!(2, regions[12]);
+        assert_eq!(5, regions[35]);
+    }
+
+    fn pattern(size: usize, box_rows: usize, box_columns: usize) -> Vec<u8> {
+        (0..size * size)
+            .map(|i| ((box_columns * ((i / size) % box_rows) + (i / size) / box_rows + i % size) % size + 1) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_random_layout() {
+        let mut rng = rand::thread_rng();
+        let solution = pattern(9, 3, 3);
+        for _ in 0..10 {
+            let regions = random_layout(&mut rng, 9, 3, 3, Some(&solution));
+            assert!(is_valid_layout(&regions, 9));
+            for r in 0..9 {
+                let mut digits: Vec<u8> = (0..81).filter(|&i| regions[i] == r).map(|i| solution[i]).collect();
+                digits.sort_unstable();
+                assert_eq!((1..=9).collect::<Vec<u8>>(), digits);
+            }
+        }
+        assert!(is_valid_layout(&random_layout(&mut rng, 9, 3, 3, None), 9));
+        assert!(is_valid_layout(&random_layout(&mut rng, 6, 2, 3, None), 6));
+
+        let mut broken = box_regions(9, 3, 3);
+        broken.swap(0, 80);
+        assert!(!is_valid_layout(&broken, 9));
+    }
+}
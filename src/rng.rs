@@ -0,0 +1,75 @@
+use rand::{Error, RngCore};
+
+// SplitMix64, 算法固定不变, 相同种子在任何平台和版本上都得到相同序列
+#[derive(Clone, Debug)]
+pub struct StableRng {
+    state: u64,
+}
+
+impl StableRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // [0, n) 内的整数, 不依赖 rand 的分布实现
+    pub fn below(&mut self, n: usize) -> usize {
+        debug_assert!(n > 0);
+
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+impl RngCore for StableRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::*;
+
+    #[test]
+    fn test_stable_sequence() {
+        // 序列改变会导致以前的每日题目改变
+        let mut rng = StableRng::new(1234567);
+        assert_eq!(6457827717110365317, rng.next_u64());
+        assert_eq!(3203168211198807973, rng.next_u64());
+
+        let mut items: Vec<u8> = (1..=9).collect();
+        rng.shuffle(&mut items);
+        let mut again = StableRng::new(1234567);
+        again.next_u64();
+        again.next_u64();
+        let mut other: Vec<u8> = (1..=9).collect();
+        again.shuffle(&mut other);
+        assert_eq!(items, other);
+    }
+}
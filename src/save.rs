@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cage::Cage;
+use crate::difficulty::Difficulty;
+use crate::score::GameStats;
+use crate::variant::Variant;
+
+// 存档内容, 以 JSON 保存
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    pub box_rows: usize,
+    pub box_columns: usize,
+    pub variant: Variant,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // 题目给出的数字
+    pub givens: String,
+    // 当前盘面, 包括玩家填入的数字
+    pub values: String,
+    // 不规则区域, 只有 Jigsaw 使用
+    #[serde(default)]
+    pub regions: Vec<u8>,
+    #[serde(default)]
+    pub cages: Vec<Cage>,
+    // 每个格子的笔记
+    #[serde(default)]
+    pub notes: Vec<u32>,
+    // 通过推理排除的候选数
+    #[serde(default)]
+    pub excluded: Vec<u32>,
+    #[serde(default)]
+    pub stats: GameStats,
+    pub elapsed_ms: u64,
+    pub paused: bool,
+}
+
+impl SaveData {
+    pub const VERSION: u32 = 1;
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+// 单局统计
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameStats {
+    // 与答案不符或与已填数字冲突的输入次数
+    pub mistakes: u32,
+    pub hints: u32,
+    pub undos: u32,
+    // 添加笔记的次数
+    pub notes: u32,
+    pub elapsed_ms: u64,
+    pub score: u32,
+}
+
+// 计分规则: 从 base 中扣除各项惩罚, 最低为 0
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScoreConfig {
+    pub base: u32,
+    pub mistake_penalty: u32,
+    pub hint_penalty: u32,
+    pub undo_penalty: u32,
+    pub note_penalty: u32,
+    // 每分钟扣除的分数
+    pub minute_penalty: u32,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            base: 10_000,
+            mistake_penalty: 500,
+            hint_penalty: 300,
+            undo_penalty: 50,
+            note_penalty: 0,
+            minute_penalty: 100,
+        }
+    }
+}
+
+impl ScoreConfig {
+    pub fn score(&self, stats: &GameStats) -> u32 {
+        let minutes = (stats.elapsed_ms / 60_000).min(u32::MAX as u64) as u32;
+        let penalty = [
+            (stats.mistakes, self.mistake_penalty),
+            (stats.hints, self.hint_penalty),
+            (stats.undos, self.undo_penalty),
+            (stats.notes, self.note_penalty),
+            (minutes, self.minute_penalty),
+        ].iter().fold(0u32, |acc, &(count, each)| acc.saturating_add(count.saturating_mul(each)));
+
+        self.base.saturating_sub(penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::score::*;
+
+    #[test]
+    fn test_score() {
+        let config = ScoreConfig::default();
+        let mut stats = GameStats::default();
+        assert_eq!(10_000, config.score(&stats));
+
+        stats.mistakes = 2;
+        stats.hints = 1;
+        stats.undos = 4;
+        stats.notes = 30;
+        stats.elapsed_ms = 5 * 60_000 + 59_999;
+        assert_eq!(10_000 - 1000 - 300 - 200 - 500, config.score(&stats));
+
+        stats.mistakes = u32::MAX;
+        assert_eq!(0, config.score(&stats));
+
+        let config = ScoreConfig { note_penalty: 10, ..ScoreConfig::default() };
+        stats.mistakes = 0;
+        assert_eq!(10_000 - 300 - 200 - 500 - 300, config.score(&stats));
+    }
+}
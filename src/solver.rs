@@ -0,0 +1,325 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::cage::{self, Cage, Combinations};
+use crate::job::Progress;
+use crate::units::Units;
+
+// 基于位掩码的回溯求解器, 每次选择候选数最少的格子
+pub struct Solver<'a> {
+    units: &'a Units,
+    grid: Vec<u8>,
+    // 每个 unit 已经使用的数字
+    used: Vec<u32>,
+    limit: usize,
+    solutions: usize,
+    found: Vec<Vec<u8>>,
+    nodes: usize,
+    node_limit: usize,
+    // 尝试数字的顺序
+    order: Vec<u8>,
+    cages: &'a [Cage],
+    cage_of: Vec<Option<usize>>,
+    combinations: Option<Combinations>,
+    // 取消后尽快结束搜索
+    progress: Option<&'a Progress>,
+    cancelled: bool,
+}
+
+impl<'a> Solver<'a> {
+    // grid 中 0 表示空格, 已填数字互相冲突时返回 None
+    pub fn new(units: &'a Units, grid: &[u8]) -> Option<Self> {
+        Self::with_cages(units, &[], grid)
+    }
+
+    pub fn with_cages(units: &'a Units, cages: &'a [Cage], grid: &[u8]) -> Option<Self> {
+        debug_assert_eq!(grid.len(), units.cell_count());
+
+        let mut solver = Self {
+            units,
+            grid: vec![0; grid.len()],
+            used: vec![0; units.units().len()],
+            limit: 1,
+            solutions: 0,
+            found: vec![],
+            nodes: 0,
+            node_limit: usize::MAX,
+            order: (1..=units.size() as u8).collect(),
+            cages,
+            cage_of: cage::cage_map(cages, grid.len()),
+            combinations: (!cages.is_empty()).then(|| Combinations::new(units.size())),
+            progress: None,
+            cancelled: false,
+        };
+
+        for (cell, &val) in grid.iter().enumerate() {
+            if val == 0 {
+                continue;
+            }
+
+            if solver.candidates(cell) & (1 << val) == 0 {
+                return None;
+            }
+            solver.place(cell, val);
+        }
+
+        Some(solver)
+    }
+
+    // 搜索节点数超过 limit 后放弃, 避免在无解的盘面上耗费太久
+    pub fn node_limit(mut self, limit: usize) -> Self {
+        self.node_limit = limit;
+        self
+    }
+
+    // 随机打乱尝试顺序, 用于生成随机的解
+    pub fn shuffle<R: Rng>(mut self, rng: &mut R) -> Self {
+        self.order.shuffle(rng);
+        self
+    }
+
+    // progress 被取消后放弃搜索, 按超时处理
+    pub fn cancel_on(mut self, progress: &'a Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn aborted(&self) -> bool {
+        self.cancelled || self.nodes > self.node_limit
+    }
+
+    // 每隔一段节点检查一次取消标志
+    fn visit(&mut self) {
+        self.nodes += 1;
+        if self.nodes % 1024 == 1 {
+            self.cancelled |= self.progress.is_some_and(|p| p.is_cancelled());
+        }
+    }
+
+    fn all_digits(&self) -> u32 {
+        ((1 << self.units.size()) - 1) << 1
+    }
+
+    fn candidates(&self, cell: usize) -> u32 {
+        let mut mask = self.all_digits();
+        for &unit in self.units.units_of(cell) {
+            mask &= !self.used[unit];
+        }
+
+        if let (Some(cage), Some(combinations)) = (self.cage_of[cell], &self.combinations) {
+            mask &= self.cages[cage].allowed_digits(combinations, &self.grid);
+        }
+
+        mask
+    }
+
+    fn place(&mut self, cell: usize, val: u8) {
+        self.grid[cell] = val;
+        for &unit in self.units.units_of(cell) {
+            self.used[unit] |= 1 << val;
+        }
+    }
+
+    fn unplace(&mut self, cell: usize) {
+        let val = self.grid[cell];
+        self.grid[cell] = 0;
+        for &unit in self.units.units_of(cell) {
+            self.used[unit] &= !(1 << val);
+        }
+    }
+
+    fn search(&mut self) {
+        self.visit();
+        if self.aborted() {
+            return;
+        }
+
+        let mut best: Option<(usize, u32)> = None;
+        for cell in 0..self.grid.len() {
+            if self.grid[cell] != 0 {
+                continue;
+            }
+
+            let mask = self.candidates(cell);
+            if mask == 0 {
+                return;
+            }
+
+            if best.is_none_or(|(_, m)| mask.count_ones() < m.count_ones()) {
+                best = Some((cell, mask));
+                if mask.count_ones() == 1 {
+                    break;
+                }
+            }
+        }
+
+        let (cell, mask) = match best {
+            Some(x) => x,
+            None => {
+                self.solutions += 1;
+                self.found.push(self.grid.clone());
+                return;
+            }
+        };
+
+        for i in 0..self.order.len() {
+            let val = self.order[i];
+            if mask & (1 << val) == 0 {
+                continue;
+            }
+
+            self.place(cell, val);
+            self.search();
+            self.unplace(cell);
+
+            if self.solutions >= self.limit || self.aborted() {
+                return;
+            }
+        }
+    }
+
+    // 统计解的个数, 达到 limit 后停止
+    pub fn count_solutions(&mut self, limit: usize) -> usize {
+        self.limit = limit;
+        self.search();
+        self.solutions
+    }
+
+    pub fn solve(&mut self) -> Option<Vec<u8>> {
+        self.limit = 1;
+        self.search();
+        self.found.pop()
+    }
+
+    // 随机的一个解: 在候选数最少的格子中随机选一个, 候选数按随机顺序尝试.
+    // 超过 node_limit 时返回 None, 可以换一个随机序列重试
+    pub fn solve_random<R: Rng>(&mut self, rng: &mut R) -> Option<Vec<u8>> {
+        self.limit = 1;
+        self.search_random(rng);
+        self.found.pop()
+    }
+
+    fn search_random<R: Rng>(&mut self, rng: &mut R) {
+        self.visit();
+        if self.aborted() {
+            return;
+        }
+
+        let mut fewest = u32::MAX;
+        let mut cells = vec![];
+        for cell in 0..self.grid.len() {
+            if self.grid[cell] != 0 {
+                continue;
+            }
+
+            let count = self.candidates(cell).count_ones();
+            if count == 0 {
+                return;
+            }
+            if count < fewest {
+                fewest = count;
+                cells.clear();
+            }
+            if count == fewest {
+                cells.push(cell);
+            }
+        }
+
+        let cell = match cells.choose(rng) {
+            Some(&cell) => cell,
+            None => {
+                self.solutions += 1;
+                self.found.push(self.grid.clone());
+                return;
+            }
+        };
+
+        let mask = self.candidates(cell);
+        let mut digits: Vec<u8> = (1..=self.units.size() as u8).filter(|&v| mask & (1 << v) != 0).collect();
+        digits.shuffle(rng);
+        for val in digits {
+            self.place(cell, val);
+            self.search_random(rng);
+            self.unplace(cell);
+
+            if self.solutions >= self.limit || self.aborted() {
+                return;
+            }
+        }
+    }
+
+    // count_solutions 找到的解
+    pub fn solutions(&self) -> &[Vec<u8>] {
+        &self.found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::*;
+
+    fn count_solutions(units: &Units, grid: &[u8], limit: usize) -> usize {
+        Solver::new(units, grid).map_or(0, |mut s| s.count_solutions(limit))
+    }
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn parse(s: &str) -> Vec<u8> {
+        s.bytes().map(|b| b - b'0').collect()
+    }
+
+    #[test]
+    fn test_solve() {
+        let units = Units::standard();
+        let solution = Solver::new(&units, &parse(PUZZLE)).unwrap().solve().unwrap();
+
+        assert_eq!(parse("534678912672195348198342567859761423426853791713924856961537284287419635345286179"), solution);
+        assert_eq!(1, count_solutions(&units, &parse(PUZZLE), 2));
+    }
+
+    #[test]
+    fn test_solve_random() {
+        let units = Units::standard();
+        let mut rng = rand::thread_rng();
+        let grid = parse(PUZZLE);
+        let solution = Solver::new(&units, &grid).unwrap().solve_random(&mut rng);
+        assert_eq!(Solver::new(&units, &grid).unwrap().solve(), solution);
+
+        let a = Solver::new(&units, &[0; 81]).unwrap().solve_random(&mut rng).unwrap();
+        let b = Solver::new(&units, &[0; 81]).unwrap().solve_random(&mut rng).unwrap();
+        assert_ne!(a, b);
+        assert!(Solver::new(&units, &a).is_some());
+
+        assert!(Solver::new(&units, &[0; 81]).unwrap().node_limit(10).solve_random(&mut rng).is_none());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let units = Units::standard();
+        let progress = Progress::new();
+        let mut solver = Solver::new(&units, &[0; 81]).unwrap().cancel_on(&progress);
+        assert!(solver.solve().is_some());
+        assert!(!solver.aborted());
+
+        progress.cancel();
+        let mut solver = Solver::new(&units, &[0; 81]).unwrap().cancel_on(&progress);
+        assert_eq!(0, solver.count_solutions(2));
+        assert!(solver.aborted());
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let units = Units::standard();
+        assert_eq!(2, count_solutions(&units, &[0; 81], 2));
+
+        let mut grid = parse(PUZZLE);
+        grid[0] = 0;
+        assert_eq!(1, count_solutions(&units, &grid, 2));
+
+        // 冲突的盘面无解
+        grid[2] = 5;
+        grid[3] = 5;
+        assert_eq!(0, count_solutions(&units, &grid, 2));
+        assert!(Solver::new(&units, &grid).is_none());
+    }
+}
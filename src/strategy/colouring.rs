@@ -0,0 +1,96 @@
+use crate::strategy::{Grid, Step, Technique};
+
+// 单个数字的强链着色: 单元内只有两个位置的格子互为共轭, 两者颜色相反.
+// 同色的两格互相可见时该颜色全部为假; 能同时看到两种颜色的格子不能填此数字
+pub fn simple_colouring(grid: &Grid) -> Option<Step> {
+    let cell_count = grid.values().len();
+
+    for digit in 1..=grid.size() as u8 {
+        let mut links: Vec<Vec<usize>> = vec![vec![]; cell_count];
+        for unit in 0..grid.units().units().len() {
+            let cells = grid.cells_with(unit, digit);
+            if cells.len() == 2 {
+                links[cells[0]].push(cells[1]);
+                links[cells[1]].push(cells[0]);
+            }
+        }
+
+        let mut colour: Vec<Option<bool>> = vec![None; cell_count];
+        for start in 0..cell_count {
+            if links[start].is_empty() || colour[start].is_some() {
+                continue;
+            }
+
+            // 给整条链着色
+            let mut chain = vec![start];
+            colour[start] = Some(true);
+            let mut i = 0;
+            while i < chain.len() {
+                let cell = chain[i];
+                for &next in links[cell].iter() {
+                    if colour[next].is_none() {
+                        colour[next] = colour[cell].map(|c| !c);
+                        chain.push(next);
+                    }
+                }
+                i += 1;
+            }
+
+            let mut reasons = chain.clone();
+            reasons.sort_unstable();
+            let mut step = Step::new(Technique::SimpleColouring, reasons);
+
+            // 同色互见
+            for side in [true, false].iter() {
+                let same: Vec<usize> = chain.iter().copied().filter(|&c| colour[c] == Some(*side)).collect();
+                let clash = same.iter().enumerate().any(|(j, &a)| same[j + 1..].iter().any(|&b| grid.sees(a, b)));
+                if clash {
+                    for &cell in same.iter() {
+                        step.eliminate(grid, cell, digit);
+                    }
+                }
+            }
+
+            // 两色夹击
+            for cell in (0..cell_count).filter(|&c| grid.has(c, digit) && colour[c].is_none()) {
+                let sees = |side: bool| chain.iter().any(|&c| colour[c] == Some(side) && grid.sees(cell, c));
+                if sees(true) && sees(false) {
+                    step.eliminate(grid, cell, digit);
+                }
+            }
+
+            if let Some(step) = step.useful() {
+                return Some(step);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_colour_trap() {
+        // 数字 1 的链: (0,0)-(0,4) 行共轭, (0,4)-(4,4) 列共轭, (4,4)-(4,0) 行共轭
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        for cell in 0..81 {
+            let (row, column) = (cell / 9, cell % 9);
+            let cut = (row == 0 && cell != 0 && cell != 4)
+                || (column == 4 && cell != 4 && cell != 40)
+                || (row == 4 && cell != 36 && cell != 40);
+            if cut {
+                grid.eliminate(cell, 1);
+            }
+        }
+
+        let step = Technique::SimpleColouring.find(&grid).unwrap();
+        assert_eq!(vec![0, 4, 36, 40], step.reasons);
+        // 第 0 列其它格子同时看到 (0,0) 和 (4,0)
+        assert!(step.eliminations.contains(&Candidate::new(9, 1)));
+        assert!(step.eliminations.contains(&Candidate::new(72, 1)));
+        assert!(step.eliminations.iter().all(|e| e.digit == 1 && !step.reasons.contains(&e.cell)));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::strategy::{combinations, Grid, Step, Technique};
+use crate::units::UnitKind;
+
+// n 行中某个数字的位置都落在同样的 n 列上, 这 n 列的其它行不能再填这个数字 (行列互换亦然).
+// n 为 2, 3, 4 时分别为 X-Wing, Swordfish, Jellyfish
+pub fn find(grid: &Grid, n: usize, technique: Technique) -> Option<Step> {
+    let size = grid.size();
+    let orientations = [(UnitKind::Row, UnitKind::Column), (UnitKind::Column, UnitKind::Row)];
+
+    for &(base_kind, cover_kind) in orientations.iter() {
+        let base_units: Vec<usize> = (0..grid.units().units().len())
+            .filter(|&u| grid.units().unit(u).kind() == base_kind)
+            .collect();
+        // 格子在覆盖方向上的编号
+        let line_of = |cell: usize| if cover_kind == UnitKind::Column { cell % size } else { cell / size };
+
+        for digit in 1..=size as u8 {
+            let bases: Vec<(usize, Vec<usize>)> = base_units.iter()
+                .map(|&u| (u, grid.cells_with(u, digit)))
+                .filter(|(_, cells)| (2..=n).contains(&cells.len()))
+                .collect();
+
+            for group in combinations(&(0..bases.len()).collect::<Vec<_>>(), n) {
+                let mut covers: Vec<usize> = group.iter()
+                    .flat_map(|&i| bases[i].1.iter().map(|&c| line_of(c)))
+                    .collect();
+                covers.sort_unstable();
+                covers.dedup();
+                if covers.len() != n {
+                    continue;
+                }
+
+                let reasons: Vec<usize> = group.iter().flat_map(|&i| bases[i].1.iter().copied()).collect();
+                let base_cells: Vec<usize> = group.iter()
+                    .flat_map(|&i| grid.units().unit(bases[i].0).cells().iter().copied())
+                    .collect();
+
+                let mut step = Step::new(technique, reasons);
+                for cell in 0..size * size {
+                    if covers.contains(&line_of(cell)) && !base_cells.contains(&cell) {
+                        step.eliminate(grid, cell, digit);
+                    }
+                }
+
+                if let Some(step) = step.useful() {
+                    return Some(step);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_x_wing() {
+        // 第 1, 7 行中的 5 只在第 2, 6 列
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        for row in [1, 7].iter() {
+            for column in (0..9).filter(|c| *c != 2 && *c != 6) {
+                grid.eliminate(row * 9 + column, 5);
+            }
+        }
+
+        let step = Technique::XWing.find(&grid).unwrap();
+        assert_eq!(vec![11, 15, 65, 69], step.reasons);
+        assert_eq!(14, step.eliminations.len());
+        assert!(step.eliminations.iter().all(|e| e.digit == 5 && (e.cell % 9 == 2 || e.cell % 9 == 6)));
+        assert!(Technique::Swordfish.find(&grid).is_none());
+    }
+
+    #[test]
+    fn test_swordfish() {
+        // 第 0, 4, 8 列中的 3 只在第 1, 4, 7 行, 每列只有两个位置
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        let keep = [(0, [1, 4]), (4, [4, 7]), (8, [1, 7])];
+        for (column, rows) in keep.iter() {
+            for row in (0..9).filter(|r| !rows.contains(r)) {
+                grid.eliminate(row * 9 + column, 3);
+            }
+        }
+
+        assert!(Technique::XWing.find(&grid).is_none());
+        let step = Technique::Swordfish.find(&grid).unwrap();
+        assert_eq!(6, step.reasons.len());
+        assert_eq!(18, step.eliminations.len());
+        assert!(step.eliminations.iter().all(|e| [1, 4, 7].contains(&(e.cell / 9))));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::strategy::{Grid, Step, Technique};
+use crate::units::UnitKind;
+
+fn is_line(kind: UnitKind) -> bool {
+    kind == UnitKind::Row || kind == UnitKind::Column
+}
+
+// 单元 A 中某个数字只出现在与单元 B 相交的格子里, B 的其它格子不能再填这个数字.
+// A 为宫时称为 pointing, A 为行列时称为 claiming
+pub fn find(grid: &Grid, claiming: bool) -> Option<Step> {
+    let units = grid.units().units();
+    let technique = if claiming { Technique::Claiming } else { Technique::PointingPair };
+
+    for (a, unit) in units.iter().enumerate() {
+        if is_line(unit.kind()) != claiming {
+            continue;
+        }
+
+        for digit in 1..=grid.size() as u8 {
+            let cells = grid.cells_with(a, digit);
+            if cells.len() < 2 {
+                continue;
+            }
+
+            for (b, other) in units.iter().enumerate() {
+                if b == a || !cells.iter().all(|c| other.cells().contains(c)) {
+                    continue;
+                }
+
+                let mut step = Step::new(technique, cells.clone());
+                for &cell in other.cells().iter().filter(|c| !unit.cells().contains(c)) {
+                    step.eliminate(grid, cell, digit);
+                }
+
+                if let Some(step) = step.useful() {
+                    return Some(step);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_pointing_claiming() {
+        // 宫 0 中的 1 只在第 0 行
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        for cell in [9, 10, 11, 18, 19, 20].iter() {
+            grid.set_candidates(*cell, 0b1111111100);
+        }
+
+        let step = Technique::PointingPair.find(&grid).unwrap();
+        assert_eq!(vec![0, 1, 2], step.reasons);
+        assert_eq!(6, step.eliminations.len());
+        assert!(step.eliminations.iter().all(|e| e.digit == 1 && (3..9).contains(&e.cell)));
+
+        // 第 0 行的 1 只在宫 0
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        for cell in 3..9 {
+            grid.set_candidates(cell, 0b1111111100);
+        }
+        assert!(Technique::PointingPair.find(&grid).is_none());
+
+        let step = Technique::Claiming.find(&grid).unwrap();
+        assert_eq!(vec![0, 1, 2], step.reasons);
+        assert!(step.eliminations.iter().all(|e| e.digit == 1 && [9, 10, 11, 18, 19, 20].contains(&e.cell)));
+    }
+}
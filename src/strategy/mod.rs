@@ -0,0 +1,357 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+use crate::units::{UnitKind, Units};
+
+mod colouring;
+mod fish;
+mod intersections;
+mod singles;
+mod subsets;
+mod uniqueness;
+mod wings;
+
+// 人工解题技巧, 按难度从低到高排列
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    NakedPair,
+    HiddenPair,
+    PointingPair,
+    Claiming,
+    NakedTriple,
+    HiddenTriple,
+    NakedQuad,
+    HiddenQuad,
+    XWing,
+    SimpleColouring,
+    XYWing,
+    XYZWing,
+    Swordfish,
+    Jellyfish,
+    UniqueRectangle,
+    Bug1,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 18] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::PointingPair,
+        Technique::Claiming,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::NakedQuad,
+        Technique::HiddenQuad,
+        Technique::XWing,
+        Technique::SimpleColouring,
+        Technique::XYWing,
+        Technique::XYZWing,
+        Technique::Swordfish,
+        Technique::Jellyfish,
+        Technique::UniqueRectangle,
+        Technique::Bug1,
+    ];
+
+    // 难度等级, 即在 ALL 中的位置
+    pub fn level(self) -> usize {
+        Technique::ALL.iter().position(|&t| t == self).unwrap()
+    }
+
+    // C 接口传入的值, 无效时返回 None
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Technique::ALL.get(value as usize).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked Single",
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::NakedPair => "Naked Pair",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::PointingPair => "Pointing Pair",
+            Technique::Claiming => "Claiming",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::NakedQuad => "Naked Quad",
+            Technique::HiddenQuad => "Hidden Quad",
+            Technique::XWing => "X-Wing",
+            Technique::SimpleColouring => "Simple Colouring",
+            Technique::XYWing => "XY-Wing",
+            Technique::XYZWing => "XYZ-Wing",
+            Technique::Swordfish => "Swordfish",
+            Technique::Jellyfish => "Jellyfish",
+            Technique::UniqueRectangle => "Unique Rectangle",
+            Technique::Bug1 => "BUG+1",
+        }
+    }
+
+    // 在 grid 上找一处可以使用此技巧的地方
+    pub fn find(self, grid: &Grid) -> Option<Step> {
+        match self {
+            Technique::NakedSingle => singles::naked_single(grid),
+            Technique::HiddenSingle => singles::hidden_single(grid),
+            Technique::NakedPair => subsets::naked(grid, 2, self),
+            Technique::NakedTriple => subsets::naked(grid, 3, self),
+            Technique::NakedQuad => subsets::naked(grid, 4, self),
+            Technique::HiddenPair => subsets::hidden(grid, 2, self),
+            Technique::HiddenTriple => subsets::hidden(grid, 3, self),
+            Technique::HiddenQuad => subsets::hidden(grid, 4, self),
+            Technique::PointingPair => intersections::find(grid, false),
+            Technique::Claiming => intersections::find(grid, true),
+            Technique::XWing => fish::find(grid, 2, self),
+            Technique::Swordfish => fish::find(grid, 3, self),
+            Technique::Jellyfish => fish::find(grid, 4, self),
+            Technique::SimpleColouring => colouring::simple_colouring(grid),
+            Technique::XYWing => wings::xy_wing(grid),
+            Technique::XYZWing => wings::xyz_wing(grid),
+            Technique::UniqueRectangle => uniqueness::unique_rectangle(grid),
+            Technique::Bug1 => uniqueness::bug1(grid),
+        }
+    }
+}
+
+// 格子 cell 中的数字 digit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Candidate {
+    pub cell: usize,
+    pub digit: u8,
+}
+
+impl Candidate {
+    pub fn new(cell: usize, digit: u8) -> Self {
+        Self { cell, digit }
+    }
+}
+
+// 一次推理的结果: 可以填入的数字、可以删除的候选数和推理依据的格子
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub technique: Technique,
+    pub placements: Vec<Candidate>,
+    pub eliminations: Vec<Candidate>,
+    pub reasons: Vec<usize>,
+}
+
+impl Step {
+    fn new(technique: Technique, reasons: Vec<usize>) -> Self {
+        Self { technique, placements: vec![], eliminations: vec![], reasons }
+    }
+
+    fn place(mut self, cell: usize, digit: u8) -> Self {
+        self.placements.push(Candidate::new(cell, digit));
+        self
+    }
+
+    // 只记录 grid 中确实存在的候选数
+    fn eliminate(&mut self, grid: &Grid, cell: usize, digit: u8) {
+        let candidate = Candidate::new(cell, digit);
+        if grid.has(cell, digit) && !self.eliminations.contains(&candidate) {
+            self.eliminations.push(candidate);
+        }
+    }
+
+    // 所有格子和数字都在 size x size 的盘面范围内, 用于检查外部传入的步骤
+    pub fn fits(&self, size: usize) -> bool {
+        let valid = |c: &Candidate| c.cell < size * size && c.digit >= 1 && c.digit as usize <= size;
+        self.placements.iter().all(valid)
+            && self.eliminations.iter().all(valid)
+            && self.reasons.iter().all(|&cell| cell < size * size)
+    }
+
+    // 没有任何作用时返回 None
+    fn useful(self) -> Option<Self> {
+        (!self.placements.is_empty() || !self.eliminations.is_empty()).then_some(self)
+    }
+}
+
+// 候选数网格, 已填格子的候选数为 0
+#[derive(Clone, Debug)]
+pub struct Grid {
+    units: Arc<Units>,
+    values: Vec<u8>,
+    candidates: Vec<u32>,
+    // 可以使用唯一解类技巧: 没有对角线和笼子等额外约束
+    uniqueness: bool,
+}
+
+impl Grid {
+    // 按已填数字计算每格的候选数
+    pub fn new(units: Arc<Units>, values: &[u8]) -> Self {
+        let mut candidates = vec![0; values.len()];
+        let all = ((1 << units.size()) - 1) << 1;
+        for (cell, mask) in candidates.iter_mut().enumerate() {
+            if values[cell] == 0 {
+                *mask = units.peers(cell).iter().fold(all, |acc, &p| acc & !(1u32 << values[p]));
+            }
+        }
+
+        let uniqueness = units.units().iter().all(|u| u.kind() != UnitKind::Diagonal);
+
+        Self { units, values: values.to_vec(), candidates, uniqueness }
+    }
+
+    // 有额外约束时唯一矩形等技巧不成立
+    pub fn set_uniqueness(&mut self, allowed: bool) {
+        self.uniqueness = allowed;
+    }
+
+    pub fn size(&self) -> usize {
+        self.units.size()
+    }
+
+    pub fn units(&self) -> &Units {
+        &self.units
+    }
+
+    pub fn values(&self) -> &[u8] {
+        &self.values
+    }
+
+    pub fn value(&self, cell: usize) -> u8 {
+        self.values[cell]
+    }
+
+    pub fn candidates(&self, cell: usize) -> u32 {
+        self.candidates[cell]
+    }
+
+    // 替换候选数, 比如使用玩家的笔记
+    pub fn set_candidates(&mut self, cell: usize, mask: u32) {
+        if self.values[cell] == 0 {
+            self.candidates[cell] = mask;
+        }
+    }
+
+    pub fn has(&self, cell: usize, digit: u8) -> bool {
+        self.candidates[cell] & (1 << digit) != 0
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|&v| v != 0)
+    }
+
+    // 单元内含有候选数 digit 的格子
+    fn cells_with(&self, unit: usize, digit: u8) -> Vec<usize> {
+        self.units.unit(unit).cells().iter().copied().filter(|&c| self.has(c, digit)).collect()
+    }
+
+    fn sees(&self, a: usize, b: usize) -> bool {
+        self.units.is_peer(a, b)
+    }
+
+    pub fn place(&mut self, cell: usize, digit: u8) {
+        self.values[cell] = digit;
+        self.candidates[cell] = 0;
+        for &peer in self.units.peers(cell) {
+            self.candidates[peer] &= !(1 << digit);
+        }
+    }
+
+    pub fn eliminate(&mut self, cell: usize, digit: u8) {
+        self.candidates[cell] &= !(1 << digit);
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        for e in step.eliminations.iter() {
+            self.eliminate(e.cell, e.digit);
+        }
+        for p in step.placements.iter() {
+            self.place(p.cell, p.digit);
+        }
+    }
+
+    // 按 techniques 的顺序找到第一个可用的步骤
+    pub fn next_step(&self, techniques: &[Technique]) -> Option<Step> {
+        techniques.iter().find_map(|t| t.find(self))
+    }
+}
+
+fn digits(mask: u32) -> impl Iterator<Item = u8> {
+    (1..32u8).filter(move |d| mask & (1 << d) != 0)
+}
+
+// items 中所有 k 个元素的组合
+fn combinations<T: Copy>(items: &[T], k: usize) -> Vec<Vec<T>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    if items.len() < k {
+        return vec![];
+    }
+
+    let mut result = vec![];
+    for (i, &first) in items.iter().enumerate() {
+        for mut rest in combinations(&items[i + 1..], k - 1) {
+            rest.insert(0, first);
+            result.push(rest);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Solver;
+    use crate::strategy::*;
+
+    fn parse(s: &str) -> Vec<u8> {
+        s.bytes().map(|b| if b == b'.' { 0 } else { b - b'0' }).collect()
+    }
+
+    // 反复使用所有技巧, 每一步都必须与答案一致
+    fn check_sound(puzzle: &str) -> Grid {
+        let units = Units::standard();
+        let mut solver = Solver::new(&units, &parse(puzzle)).unwrap();
+        assert_eq!(1, solver.count_solutions(2));
+        let solution = solver.solutions()[0].clone();
+        let mut grid = Grid::new(units, &parse(puzzle));
+
+        while let Some(step) = grid.next_step(&Technique::ALL) {
+            for p in step.placements.iter() {
+                assert_eq!(solution[p.cell], p.digit, "{:?}", step);
+            }
+            for e in step.eliminations.iter() {
+                assert_ne!(solution[e.cell], e.digit, "{:?}", step);
+            }
+            grid.apply(&step);
+        }
+
+        grid
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::new(Units::standard(), &parse(&format!("12{}", ".".repeat(79))));
+        assert_eq!(0, grid.candidates(0));
+        assert_eq!(0b1111111000, grid.candidates(2));
+        assert_eq!(0b1111111100, grid.candidates(27));
+        assert_eq!(0b1111111110, grid.candidates(80));
+    }
+
+    #[test]
+    fn test_solve_with_techniques() {
+        let grid = check_sound("53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79");
+        assert!(grid.is_solved());
+
+        // 需要 X-Wing 等技巧的题目
+        let grid = check_sound("1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5");
+        assert!(grid.is_solved());
+
+        // 这些技巧解不出的题目, 推理到卡住为止
+        let grid = check_sound("1.......2.9.4...5...6...7...5.9.3.......7.......85..4.7.....6...3...9.8...2.....1");
+        assert!(!grid.is_solved());
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(vec![vec![1, 2], vec![1, 3], vec![2, 3]], combinations(&[1, 2, 3], 2));
+        assert_eq!(1, combinations(&[1, 2, 3], 0).len());
+        assert!(combinations(&[1, 2], 3).is_empty());
+    }
+}
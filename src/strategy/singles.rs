@@ -0,0 +1,62 @@
+use crate::strategy::{digits, Grid, Step, Technique};
+
+// 只剩一个候选数的格子
+pub fn naked_single(grid: &Grid) -> Option<Step> {
+    let cell = (0..grid.values().len()).find(|&c| grid.candidates(c).count_ones() == 1)?;
+    let digit = digits(grid.candidates(cell)).next().unwrap();
+
+    // 依据为排除了其它数字的已填格子, 每个数字取一个
+    let mut reasons = vec![];
+    for other in (1..=grid.size() as u8).filter(|&d| d != digit) {
+        if let Some(&peer) = grid.units().peers(cell).iter().find(|&&p| grid.value(p) == other) {
+            reasons.push(peer);
+        }
+    }
+
+    Some(Step::new(Technique::NakedSingle, reasons).place(cell, digit))
+}
+
+// 单元内某个数字只能填在一个格子里
+pub fn hidden_single(grid: &Grid) -> Option<Step> {
+    for unit in 0..grid.units().units().len() {
+        for digit in 1..=grid.size() as u8 {
+            let cells = grid.cells_with(unit, digit);
+            if cells.len() != 1 || grid.candidates(cells[0]).count_ones() == 1 {
+                continue;
+            }
+
+            let reasons = grid.units().unit(unit).cells().iter().copied().filter(|&c| c != cells[0]).collect();
+            return Some(Step::new(Technique::HiddenSingle, reasons).place(cells[0], digit));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_singles() {
+        // 第一行只差 9
+        let mut values = vec![0u8; 81];
+        values[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let grid = Grid::new(Units::standard(), &values);
+
+        let step = Technique::NakedSingle.find(&grid).unwrap();
+        assert_eq!(vec![Candidate::new(8, 9)], step.placements);
+        assert_eq!(8, step.reasons.len());
+
+        // 第 0 列之外的每行都有 1, 第 0 列的 1 只能填在第 0 行
+        let mut values = vec![0u8; 81];
+        for (row, column) in [(1, 3), (2, 6), (3, 1), (4, 4), (5, 7), (6, 2), (7, 5), (8, 8)].iter() {
+            values[row * 9 + column] = 1;
+        }
+        let grid = Grid::new(Units::standard(), &values);
+        assert!(Technique::NakedSingle.find(&grid).is_none());
+
+        let step = Technique::HiddenSingle.find(&grid).unwrap();
+        assert_eq!(vec![Candidate::new(0, 1)], step.placements);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::strategy::{combinations, digits, Grid, Step, Technique};
+
+// n 个格子只有 n 个候选数, 单元内其它格子不能再填这些数字
+pub fn naked(grid: &Grid, n: usize, technique: Technique) -> Option<Step> {
+    for unit in grid.units().units() {
+        let empty: Vec<usize> = unit.cells().iter().copied()
+            .filter(|&c| grid.value(c) == 0)
+            .collect();
+        let open: Vec<usize> = empty.iter().copied()
+            .filter(|&c| (2..=n as u32).contains(&grid.candidates(c).count_ones()))
+            .collect();
+
+        for cells in combinations(&open, n) {
+            let mask = cells.iter().fold(0, |acc, &c| acc | grid.candidates(c));
+            if mask.count_ones() as usize != n {
+                continue;
+            }
+
+            let mut step = Step::new(technique, cells.clone());
+            for &other in empty.iter().filter(|c| !cells.contains(c)) {
+                for digit in digits(mask) {
+                    step.eliminate(grid, other, digit);
+                }
+            }
+
+            if let Some(step) = step.useful() {
+                return Some(step);
+            }
+        }
+    }
+
+    None
+}
+
+// n 个数字在单元内只能填在 n 个格子里, 这些格子不能再填其它数字
+pub fn hidden(grid: &Grid, n: usize, technique: Technique) -> Option<Step> {
+    for idx in 0..grid.units().units().len() {
+        let open: Vec<u8> = (1..=grid.size() as u8)
+            .filter(|&d| (1..=n).contains(&grid.cells_with(idx, d).len()))
+            .collect();
+
+        for group in combinations(&open, n) {
+            let mut cells: Vec<usize> = group.iter().flat_map(|&d| grid.cells_with(idx, d)).collect();
+            cells.sort_unstable();
+            cells.dedup();
+            if cells.len() != n {
+                continue;
+            }
+
+            let mask = group.iter().fold(0u32, |acc, &d| acc | (1 << d));
+            let mut step = Step::new(technique, cells.clone());
+            for &cell in cells.iter() {
+                for digit in digits(grid.candidates(cell) & !mask) {
+                    step.eliminate(grid, cell, digit);
+                }
+            }
+
+            if let Some(step) = step.useful() {
+                return Some(step);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_naked_pair() {
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        grid.set_candidates(0, 0b110);
+        grid.set_candidates(1, 0b110);
+
+        let step = Technique::NakedPair.find(&grid).unwrap();
+        assert_eq!(vec![0, 1], step.reasons);
+        assert!(step.eliminations.contains(&Candidate::new(8, 1)));
+        assert!(step.eliminations.contains(&Candidate::new(2, 2)));
+        assert!(!step.eliminations.iter().any(|e| e.cell < 2));
+        assert!(step.placements.is_empty());
+
+        // 三个格子 {1,2} {2,3} {1,3}
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        grid.set_candidates(0, 0b0110);
+        grid.set_candidates(4, 0b1100);
+        grid.set_candidates(8, 0b1010);
+        assert!(Technique::NakedPair.find(&grid).is_none());
+
+        let step = Technique::NakedTriple.find(&grid).unwrap();
+        assert_eq!(vec![0, 4, 8], step.reasons);
+        assert_eq!(6 * 3, step.eliminations.len());
+    }
+
+    #[test]
+    fn test_hidden_pair() {
+        // 第 0 行中 1 和 2 只能填在 0, 1 两格
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        for cell in 2..9 {
+            grid.set_candidates(cell, 0b1111111000);
+        }
+
+        let step = Technique::HiddenPair.find(&grid).unwrap();
+        assert_eq!(vec![0, 1], step.reasons);
+        assert_eq!(14, step.eliminations.len());
+        assert!(step.eliminations.iter().all(|e| e.cell < 2 && e.digit > 2));
+    }
+}
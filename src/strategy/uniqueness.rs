@@ -0,0 +1,154 @@
+use crate::strategy::{digits, Grid, Step, Technique};
+use crate::units::UnitKind;
+
+fn block_of(grid: &Grid, cell: usize) -> Option<usize> {
+    grid.units().units_of(cell).iter().copied()
+        .find(|&u| matches!(grid.units().unit(u).kind(), UnitKind::Box | UnitKind::Region))
+}
+
+// 唯一矩形 1 型: 两行两列两宫的四格中三格只有 {x,y}, 第四格不能填 x 或 y, 否则有两个解
+pub fn unique_rectangle(grid: &Grid) -> Option<Step> {
+    if !grid.uniqueness {
+        return None;
+    }
+
+    let size = grid.size();
+    for r1 in 0..size {
+        for r2 in r1 + 1..size {
+            for c1 in 0..size {
+                for c2 in c1 + 1..size {
+                    let corners = [r1 * size + c1, r1 * size + c2, r2 * size + c1, r2 * size + c2];
+                    if corners.iter().any(|&c| grid.value(c) != 0) {
+                        continue;
+                    }
+
+                    let mut blocks: Vec<Option<usize>> = corners.iter().map(|&c| block_of(grid, c)).collect();
+                    blocks.sort_unstable();
+                    blocks.dedup();
+                    if blocks.len() != 2 {
+                        continue;
+                    }
+
+                    let pairs: Vec<usize> = corners.iter().copied()
+                        .filter(|&c| grid.candidates(c).count_ones() == 2)
+                        .collect();
+                    if pairs.len() != 3 || pairs.iter().any(|&c| grid.candidates(c) != grid.candidates(pairs[0])) {
+                        continue;
+                    }
+
+                    let pair = grid.candidates(pairs[0]);
+                    let fourth = *corners.iter().find(|c| !pairs.contains(c)).unwrap();
+                    if grid.candidates(fourth) & pair != pair {
+                        continue;
+                    }
+
+                    let mut step = Step::new(Technique::UniqueRectangle, corners.to_vec());
+                    for digit in digits(pair) {
+                        step.eliminate(grid, fourth, digit);
+                    }
+                    return step.useful();
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// BUG+1: 除一格有三个候选数外都只有两个, 且去掉该格多出的候选数后每个数字在每个单元中恰好出现 0 或 2 次.
+// 该格必须填这个多出的数字, 否则有多解
+pub fn bug1(grid: &Grid) -> Option<Step> {
+    if !grid.uniqueness {
+        return None;
+    }
+
+    let empty: Vec<usize> = (0..grid.values().len()).filter(|&c| grid.value(c) == 0).collect();
+    let triples: Vec<usize> = empty.iter().copied().filter(|&c| grid.candidates(c).count_ones() != 2).collect();
+    if triples.len() != 1 || grid.candidates(triples[0]).count_ones() != 3 {
+        return None;
+    }
+
+    let cell = triples[0];
+    let own = grid.units().units_of(cell);
+    for digit in digits(grid.candidates(cell)) {
+        // 多出的数字在该格所在单元中出现三次, 其余都是 0 或 2 次
+        let bug = (0..grid.units().units().len()).all(|unit| {
+            (1..=grid.size() as u8).all(|d| {
+                let count = grid.cells_with(unit, d).len();
+                if d == digit && own.contains(&unit) {
+                    count == 3
+                } else {
+                    count == 0 || count == 2
+                }
+            })
+        });
+        if bug {
+            let cells = grid.cells_with(own[0], digit);
+            return Some(Step::new(Technique::Bug1, cells).place(cell, digit));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_unique_rectangle() {
+        // (0,0) (0,3) (1,0) 只有 {1,2}, (1,3) 为 {1,2,5}
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        grid.set_candidates(0, 0b110);
+        grid.set_candidates(3, 0b110);
+        grid.set_candidates(9, 0b110);
+        grid.set_candidates(12, 0b100110);
+
+        let step = Technique::UniqueRectangle.find(&grid).unwrap();
+        assert_eq!(vec![0, 3, 9, 12], step.reasons);
+        assert_eq!(vec![Candidate::new(12, 1), Candidate::new(12, 2)], step.eliminations);
+
+        // 对角线数独中交换可能违反对角线约束
+        grid.set_uniqueness(false);
+        assert!(Technique::UniqueRectangle.find(&grid).is_none());
+    }
+
+    #[test]
+    fn test_bug1() {
+        let units = Units::grid(4, 2, 2);
+        let mut grid = Grid::new(units, &[0; 16]);
+        // 2x2 宫的 4x4 盘面, 除 (0,0) 外都只有两个候选数
+        let masks = [
+            0b01110, 0b11000, 0b00110, 0b11000,
+            0b11000, 0b00110, 0b11000, 0b00110,
+            0b00110, 0b11000, 0b00110, 0b11000,
+            0b11000, 0b00110, 0b11000, 0b00110,
+        ];
+        for (cell, &mask) in masks.iter().enumerate() {
+            grid.set_candidates(cell, mask);
+        }
+
+        // 3 在第 0 行出现三次
+        let step = Technique::Bug1.find(&grid).unwrap();
+        assert_eq!(vec![Candidate::new(0, 3)], step.placements);
+        assert_eq!(vec![0, 1, 3], step.reasons);
+    }
+
+    #[test]
+    fn test_bug1_not_bug() {
+        let units = Units::grid(4, 2, 2);
+        let mut grid = Grid::new(units, &[0; 16]);
+        // 与 test_bug1 相同, 但 (3,3) 改为 {1,3}, 第 3 行中 2 只出现一次, 不是 BUG 状态
+        let masks = [
+            0b01110, 0b11000, 0b00110, 0b11000,
+            0b11000, 0b00110, 0b11000, 0b00110,
+            0b00110, 0b11000, 0b00110, 0b11000,
+            0b11000, 0b00110, 0b11000, 0b01010,
+        ];
+        for (cell, &mask) in masks.iter().enumerate() {
+            grid.set_candidates(cell, mask);
+        }
+
+        assert!(Technique::Bug1.find(&grid).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::strategy::{digits, Grid, Step, Technique};
+
+fn empty_cells(grid: &Grid) -> impl Iterator<Item = usize> + '_ {
+    (0..grid.values().len()).filter(move |&c| grid.candidates(c) != 0)
+}
+
+// 轴心 {x,y}, 两翼 {x,z} 和 {y,z} 都能看到轴心. 无论轴心填什么, 两翼之一必为 z
+pub fn xy_wing(grid: &Grid) -> Option<Step> {
+    for pivot in empty_cells(grid).filter(|&c| grid.candidates(c).count_ones() == 2) {
+        let pivot_mask = grid.candidates(pivot);
+        let wings: Vec<usize> = grid.units().peers(pivot).iter().copied()
+            .filter(|&p| {
+                let mask = grid.candidates(p);
+                mask.count_ones() == 2 && (mask & pivot_mask).count_ones() == 1
+            })
+            .collect();
+
+        for (i, &a) in wings.iter().enumerate() {
+            for &b in wings[i + 1..].iter() {
+                let (ma, mb) = (grid.candidates(a), grid.candidates(b));
+                let z = ma & mb & !pivot_mask;
+                if z.count_ones() != 1 || ma & pivot_mask == mb & pivot_mask {
+                    continue;
+                }
+
+                if let Some(step) = eliminate_seen(grid, Technique::XYWing, &[pivot, a, b], &[a, b], z) {
+                    return Some(step);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 轴心 {x,y,z}, 两翼 {x,z} 和 {y,z}. z 必在三者之一
+pub fn xyz_wing(grid: &Grid) -> Option<Step> {
+    for pivot in empty_cells(grid).filter(|&c| grid.candidates(c).count_ones() == 3) {
+        let pivot_mask = grid.candidates(pivot);
+        let wings: Vec<usize> = grid.units().peers(pivot).iter().copied()
+            .filter(|&p| {
+                let mask = grid.candidates(p);
+                mask.count_ones() == 2 && mask & !pivot_mask == 0
+            })
+            .collect();
+
+        for (i, &a) in wings.iter().enumerate() {
+            for &b in wings[i + 1..].iter() {
+                let (ma, mb) = (grid.candidates(a), grid.candidates(b));
+                let z = ma & mb;
+                if ma == mb || z.count_ones() != 1 {
+                    continue;
+                }
+
+                if let Some(step) = eliminate_seen(grid, Technique::XYZWing, &[pivot, a, b], &[pivot, a, b], z) {
+                    return Some(step);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 从能看到 seers 中所有格子的格子里删除 z
+fn eliminate_seen(grid: &Grid, technique: Technique, reasons: &[usize], seers: &[usize], z: u32) -> Option<Step> {
+    let digit = digits(z).next().unwrap();
+    let mut step = Step::new(technique, reasons.to_vec());
+    for cell in empty_cells(grid) {
+        if !reasons.contains(&cell) && seers.iter().all(|&s| grid.sees(cell, s)) {
+            step.eliminate(grid, cell, digit);
+        }
+    }
+
+    step.useful()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::*;
+
+    #[test]
+    fn test_xy_wing() {
+        // 轴心 (0,0) {1,2}, 两翼 (0,5) {1,3} 和 (5,0) {2,3}, (5,5) 不能为 3
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        grid.set_candidates(0, 0b0110);
+        grid.set_candidates(5, 0b1010);
+        grid.set_candidates(45, 0b1100);
+
+        let step = Technique::XYWing.find(&grid).unwrap();
+        assert_eq!(vec![0, 5, 45], step.reasons);
+        assert_eq!(vec![Candidate::new(50, 3)], step.eliminations);
+    }
+
+    #[test]
+    fn test_xyz_wing() {
+        // 轴心 (0,0) {1,2,3}, 两翼 (0,5) {1,3} 和 (1,1) {2,3}, 只有 (0,1) (0,2) 能同时看到三者
+        let mut grid = Grid::new(Units::standard(), &[0; 81]);
+        grid.set_candidates(0, 0b1110);
+        grid.set_candidates(5, 0b1010);
+        grid.set_candidates(10, 0b1100);
+
+        let step = Technique::XYZWing.find(&grid).unwrap();
+        assert_eq!(vec![0, 5, 10], step.reasons);
+        assert_eq!(vec![Candidate::new(1, 3), Candidate::new(2, 3)], step.eliminations);
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::strategy::{Grid, Step, Technique};
+
+// 人工解题的完整过程, 直到解完或者卡住
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveTrace {
+    pub steps: Vec<Step>,
+    pub solved: bool,
+}
+
+impl SolveTrace {
+    // 依次使用 techniques 中第一个可用的技巧
+    pub fn solve(mut grid: Grid, techniques: &[Technique]) -> Self {
+        let mut steps = vec![];
+        while let Some(step) = grid.next_step(techniques) {
+            grid.apply(&step);
+            steps.push(step);
+        }
+
+        Self { steps, solved: grid.is_solved() }
+    }
+
+    // 用到的最难的技巧, 没有任何步骤时为 None
+    pub fn hardest(&self) -> Option<Technique> {
+        self.steps.iter().map(|s| s.technique).max_by_key(|t| t.level())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(data: &str) -> Option<Self> {
+        serde_json::from_str(data).ok()
+    }
+}
+
+// 逐步把解题过程应用到盘面上
+pub struct Replay {
+    trace: SolveTrace,
+    position: usize,
+}
+
+impl Replay {
+    pub fn new(trace: SolveTrace) -> Self {
+        Self { trace, position: 0 }
+    }
+
+    pub fn trace(&self) -> &SolveTrace {
+        &self.trace
+    }
+
+    // 已经应用的步数
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.trace.steps.len()
+    }
+
+    // 应用下一步, 已经结束或步骤与盘面尺寸不符时返回 None
+    pub fn next(&mut self, board: &mut Board) -> Option<&Step> {
+        let step = self.trace.steps.get(self.position)?;
+        if !board.apply_step(step) {
+            return None;
+        }
+        self.position += 1;
+
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::strategy::Candidate;
+    use crate::trace::*;
+    use crate::variant::Variant;
+
+    const PUZZLE: &str = "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5";
+
+    #[test]
+    fn test_trace_json() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+
+        let trace = board.solve_logically();
+        assert!(trace.solved);
+        assert!(trace.steps.iter().any(|s| s.technique == Technique::XWing));
+        assert!(trace.hardest().unwrap().level() >= Technique::XWing.level());
+        assert_eq!(None, SolveTrace { steps: vec![], solved: false }.hardest());
+
+        let json = trace.to_json();
+        assert!(json.contains("\"technique\":\"XWing\""));
+        assert_eq!(Some(trace), SolveTrace::from_json(&json));
+        assert!(SolveTrace::from_json("[]").is_none());
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        let mut replay = Replay::new(board.solve_logically());
+
+        let mut steps = 0;
+        while let Some(step) = replay.next(&mut board) {
+            for e in step.eliminations.iter() {
+                assert_eq!(0, board.candidates(e.cell / 9, e.cell % 9) & (1 << e.digit));
+            }
+            steps += 1;
+        }
+
+        assert!(replay.is_finished());
+        assert_eq!(steps, replay.position());
+        assert!(board.is_solved());
+        assert!(replay.next(&mut board).is_none());
+    }
+
+    #[test]
+    fn test_invalid_replay() {
+        let mut board = Board::empty();
+        assert!(board.load(PUZZLE));
+        let mut trace = board.solve_logically();
+
+        // 9x9 的过程不能用在 4x4 的盘面上
+        let mut small = Board::with_size(4, Variant::Standard).unwrap();
+        let mut replay = Replay::new(trace.clone());
+        assert!(replay.next(&mut small).is_none());
+        assert_eq!(0, replay.position());
+
+        // 超出范围的格子和数字
+        trace.steps[0].eliminations.push(Candidate::new(81, 1));
+        let mut replay = Replay::new(SolveTrace::from_json(&trace.to_json()).unwrap());
+        assert!(replay.next(&mut board).is_none());
+        trace.steps[0].eliminations.pop();
+        trace.steps[0].placements.push(Candidate::new(0, 10));
+        let mut replay = Replay::new(trace);
+        assert!(replay.next(&mut board).is_none());
+        assert_eq!(PUZZLE, board.export());
+    }
+}
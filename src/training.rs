@@ -0,0 +1,73 @@
+use crate::strategy::{Grid, Technique};
+use crate::trace::SolveTrace;
+
+// 训练题目的要求: 按从易到难的顺序解题时, required 的使用次数在 [min_uses, max_uses] 内,
+// 并且不需要比 ceiling 更难的技巧
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TechniqueGoal {
+    pub required: Technique,
+    pub ceiling: Technique,
+    pub min_uses: u32,
+    pub max_uses: u32,
+}
+
+impl TechniqueGoal {
+    // 恰好使用一次 required, 不超过 required 的难度
+    pub fn exactly_once(required: Technique) -> Self {
+        Self { required, ceiling: required, min_uses: 1, max_uses: 1 }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.required.level() <= self.ceiling.level() && self.min_uses <= self.max_uses
+    }
+
+    // 可以使用的技巧
+    pub fn techniques(&self) -> &'static [Technique] {
+        &Technique::ALL[..=self.ceiling.level()]
+    }
+
+    pub fn accepts(&self, grid: Grid) -> bool {
+        let trace = SolveTrace::solve(grid, self.techniques());
+        let uses = trace.steps.iter().filter(|s| s.technique == self.required).count() as u32;
+
+        trace.solved && uses >= self.min_uses && uses <= self.max_uses
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenerateError {
+    // 超时仍未找到满足要求的题目
+    Timeout = 1,
+    InvalidGoal,
+    // 提示数范围为空或超出盘面
+    InvalidClueRange,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::training::*;
+    use crate::units::Units;
+
+    fn grid(puzzle: &str) -> Grid {
+        let values: Vec<u8> = puzzle.bytes().map(|b| if b == b'.' { 0 } else { b - b'0' }).collect();
+        Grid::new(Units::standard(), &values)
+    }
+
+    #[test]
+    fn test_goal() {
+        // 需要 X-Wing 的题目
+        let puzzle = grid("1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5");
+
+        assert!(TechniqueGoal::exactly_once(Technique::XWing).accepts(puzzle.clone()));
+        assert!(!TechniqueGoal::exactly_once(Technique::PointingPair).accepts(puzzle.clone()));
+        let twice = TechniqueGoal { min_uses: 2, max_uses: 5, ..TechniqueGoal::exactly_once(Technique::XWing) };
+        assert!(!twice.accepts(puzzle.clone()));
+        let harder = TechniqueGoal { ceiling: Technique::Jellyfish, ..TechniqueGoal::exactly_once(Technique::XWing) };
+        assert!(harder.accepts(puzzle));
+
+        assert!(!TechniqueGoal { ceiling: Technique::NakedSingle, ..TechniqueGoal::exactly_once(Technique::XWing) }.is_valid());
+        assert!(!TechniqueGoal { min_uses: 2, ..TechniqueGoal::exactly_once(Technique::XWing) }.is_valid());
+    }
+}
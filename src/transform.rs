@@ -0,0 +1,401 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+// 保持数独规则的变换, 可以连续组合.
+// 新盘面第 i 格来自原盘面的 cells[i] 格, 原来的数字 v 变为 digits[v]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transform {
+    box_rows: usize,
+    box_columns: usize,
+    cells: Vec<usize>,
+    digits: Vec<u8>,
+}
+
+// FFI 使用的单步变换
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransformKind {
+    Transpose,
+    // 顺时针旋转 90 度
+    Rotate,
+    // 左右翻转
+    ReflectHorizontal,
+    // 上下翻转
+    ReflectVertical,
+    SwapBands,
+    SwapStacks,
+    SwapRows,
+    SwapColumns,
+}
+
+impl TransformKind {
+    // C 接口传入的值, 无效时返回 None
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TransformKind::Transpose),
+            1 => Some(TransformKind::Rotate),
+            2 => Some(TransformKind::ReflectHorizontal),
+            3 => Some(TransformKind::ReflectVertical),
+            4 => Some(TransformKind::SwapBands),
+            5 => Some(TransformKind::SwapStacks),
+            6 => Some(TransformKind::SwapRows),
+            7 => Some(TransformKind::SwapColumns),
+            _ => None,
+        }
+    }
+}
+
+impl Transform {
+    pub fn identity(box_rows: usize, box_columns: usize) -> Self {
+        let size = box_rows * box_columns;
+
+        Self {
+            box_rows,
+            box_columns,
+            cells: (0..size * size).collect(),
+            digits: (0..=size as u8).collect(),
+        }
+    }
+
+    // 随机组合宫组交换、组内行列交换、转置和数字替换
+    pub fn random<R: Rng>(rng: &mut R, box_rows: usize, box_columns: usize) -> Self {
+        let mut transform = Self::identity(box_rows, box_columns);
+        let size = transform.size();
+
+        let rows = shuffled_lines(rng, size, box_rows);
+        let columns = shuffled_lines(rng, size, box_columns);
+        transform.remap(|r, c| (rows[r], columns[c]));
+        if box_rows == box_columns && rng.gen_bool(0.5) {
+            transform.transpose();
+        }
+
+        let mut digits: Vec<u8> = (1..=size as u8).collect();
+        digits.shuffle(rng);
+        transform.permute_digits(&digits);
+
+        transform
+    }
+
+    pub fn size(&self) -> usize {
+        self.box_rows * self.box_columns
+    }
+
+    pub fn box_shape(&self) -> (usize, usize) {
+        (self.box_rows, self.box_columns)
+    }
+
+    // f 把新盘面的 (行, 列) 映射到变换前的 (行, 列)
+    fn remap<F: Fn(usize, usize) -> (usize, usize)>(&mut self, f: F) {
+        let size = self.size();
+        self.cells = (0..size * size).map(|i| {
+            let (r, c) = f(i / size, i % size);
+            self.cells[r * size + c]
+        }).collect();
+    }
+
+    pub fn transpose(&mut self) -> bool {
+        // 宫不是正方形时转置会改变宫的形状
+        if self.box_rows != self.box_columns {
+            return false;
+        }
+
+        self.remap(|r, c| (c, r));
+        true
+    }
+
+    pub fn rotate(&mut self) -> bool {
+        if self.box_rows != self.box_columns {
+            return false;
+        }
+
+        let last = self.size() - 1;
+        self.remap(|r, c| (last - c, r));
+        true
+    }
+
+    pub fn reflect_horizontal(&mut self) {
+        let last = self.size() - 1;
+        self.remap(|r, c| (r, last - c));
+    }
+
+    pub fn reflect_vertical(&mut self) {
+        let last = self.size() - 1;
+        self.remap(|r, c| (last - r, c));
+    }
+
+    // 交换两个横向宫组
+    pub fn swap_bands(&mut self, a: usize, b: usize) -> bool {
+        let band = self.box_rows;
+        if a.max(b) >= self.size() / band {
+            return false;
+        }
+
+        self.remap(|r, c| (swap_band(r, a, b, band), c));
+        true
+    }
+
+    // 交换两个纵向宫组
+    pub fn swap_stacks(&mut self, a: usize, b: usize) -> bool {
+        let stack = self.box_columns;
+        if a.max(b) >= self.size() / stack {
+            return false;
+        }
+
+        self.remap(|r, c| (r, swap_band(c, a, b, stack)));
+        true
+    }
+
+    // 同一宫组内的两行
+    pub fn swap_rows(&mut self, a: usize, b: usize) -> bool {
+        if a.max(b) >= self.size() || a / self.box_rows != b / self.box_rows {
+            return false;
+        }
+
+        self.remap(|r, c| (swap(r, a, b), c));
+        true
+    }
+
+    pub fn swap_columns(&mut self, a: usize, b: usize) -> bool {
+        if a.max(b) >= self.size() || a / self.box_columns != b / self.box_columns {
+            return false;
+        }
+
+        self.remap(|r, c| (r, swap(c, a, b)));
+        true
+    }
+
+    pub fn apply_kind(&mut self, kind: TransformKind, a: usize, b: usize) -> bool {
+        match kind {
+            TransformKind::Transpose => self.transpose(),
+            TransformKind::Rotate => self.rotate(),
+            TransformKind::ReflectHorizontal => {
+                self.reflect_horizontal();
+                true
+            }
+            TransformKind::ReflectVertical => {
+                self.reflect_vertical();
+                true
+            }
+            TransformKind::SwapBands => self.swap_bands(a, b),
+            TransformKind::SwapStacks => self.swap_stacks(a, b),
+            TransformKind::SwapRows => self.swap_rows(a, b),
+            TransformKind::SwapColumns => self.swap_columns(a, b),
+        }
+    }
+
+    // 数字 v 替换为 digits[v - 1], digits 必须是 1..=size 的排列
+    pub fn permute_digits(&mut self, digits: &[u8]) -> bool {
+        let size = self.size();
+        let mut seen = 0u32;
+        for &d in digits.iter() {
+            if d == 0 || d as usize > size {
+                return false;
+            }
+            seen |= 1 << d;
+        }
+        if digits.len() != size || seen.count_ones() as usize != size {
+            return false;
+        }
+
+        for d in self.digits.iter_mut().skip(1) {
+            *d = digits[*d as usize - 1];
+        }
+        true
+    }
+
+    pub fn digit(&self, digit: u8) -> u8 {
+        self.digits[digit as usize]
+    }
+
+    // 原盘面 cell 格在新盘面的位置
+    pub fn target(&self, cell: usize) -> usize {
+        self.cells.iter().position(|&c| c == cell).unwrap()
+    }
+
+    // 移动格子并替换数字, 0 表示空格
+    pub fn apply(&self, values: &[u8]) -> Vec<u8> {
+        self.cells.iter().map(|&c| self.digits[values[c] as usize]).collect()
+    }
+
+    // 移动格子并替换候选数或笔记的位掩码
+    pub fn apply_masks(&self, masks: &[u32]) -> Vec<u32> {
+        self.cells.iter().map(|&c| self.mask(masks[c])).collect()
+    }
+
+    pub fn mask(&self, mask: u32) -> u32 {
+        (1..self.digits.len())
+            .filter(|&d| mask & (1 << d) != 0)
+            .fold(0, |acc, d| acc | (1 << self.digits[d]))
+    }
+
+    // 只移动格子, 比如区域编号
+    pub fn move_cells<T: Clone>(&self, items: &[T]) -> Vec<T> {
+        self.cells.iter().map(|&c| items[c].clone()).collect()
+    }
+
+    pub fn keeps_digits(&self) -> bool {
+        self.digits.iter().enumerate().all(|(i, &d)| i == d as usize)
+    }
+
+    // 相邻的格子仍然相邻, 不规则区域和笼子保持连通
+    pub fn is_geometric(&self) -> bool {
+        let size = self.size();
+        let adjacent = |a: usize, b: usize| {
+            let (ra, ca, rb, cb) = (a / size, a % size, b / size, b % size);
+            ra.abs_diff(rb) + ca.abs_diff(cb) == 1
+        };
+
+        (0..size * size).all(|i| {
+            let (r, c) = (i / size, i % size);
+            (c + 1 == size || adjacent(self.cells[i], self.cells[i + 1]))
+                && (r + 1 == size || adjacent(self.cells[i], self.cells[i + size]))
+        })
+    }
+
+    // 两条对角线仍然映射到对角线上
+    pub fn keeps_diagonals(&self) -> bool {
+        let size = self.size();
+        let main: Vec<usize> = (0..size).map(|i| self.cells[i * size + i]).collect();
+        let anti: Vec<usize> = (0..size).map(|i| self.cells[i * size + size - 1 - i]).collect();
+        let on_main = |cells: &[usize]| cells.iter().all(|&c| c / size == c % size);
+        let on_anti = |cells: &[usize]| cells.iter().all(|&c| c / size + c % size == size - 1);
+
+        (on_main(&main) && on_anti(&anti)) || (on_anti(&main) && on_main(&anti))
+    }
+}
+
+fn swap(line: usize, a: usize, b: usize) -> usize {
+    if line == a {
+        b
+    } else if line == b {
+        a
+    } else {
+        line
+    }
+}
+
+fn swap_band(line: usize, a: usize, b: usize, band: usize) -> usize {
+    swap(line / band, a, b) * band + line % band
+}
+
+// 宫组之间和宫组内都随机排列
+fn shuffled_lines<R: Rng>(rng: &mut R, size: usize, band: usize) -> Vec<usize> {
+    let mut bands: Vec<usize> = (0..size / band).collect();
+    bands.shuffle(rng);
+
+    let mut lines = vec![];
+    for b in bands {
+        let mut inner: Vec<usize> = (0..band).collect();
+        inner.shuffle(rng);
+        lines.extend(inner.into_iter().map(|i| b * band + i));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canonical::canonical_form;
+    use crate::solver::Solver;
+    use crate::transform::*;
+    use crate::units::Units;
+
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn parse(s: &str) -> Vec<u8> {
+        s.bytes().map(|b| b - b'0').collect()
+    }
+
+    fn is_valid(values: &[u8]) -> bool {
+        Solver::new(&Units::standard(), values).is_some()
+    }
+
+    #[test]
+    fn test_single_steps() {
+        let values = parse(SOLUTION);
+
+        let mut t = Transform::identity(3, 3);
+        assert!(t.transpose());
+        assert_eq!(values[9], t.apply(&values)[1]);
+
+        let mut t = Transform::identity(3, 3);
+        assert!(t.rotate());
+        // 左下角转到左上角
+        assert_eq!(values[8 * 9], t.apply(&values)[0]);
+        assert_eq!(8, t.target(0));
+        for _ in 0..3 {
+            t.rotate();
+        }
+        assert_eq!(Transform::identity(3, 3), t);
+
+        let mut t = Transform::identity(3, 3);
+        t.reflect_horizontal();
+        assert_eq!(values[8], t.apply(&values)[0]);
+        t.reflect_vertical();
+        assert_eq!(values[80], t.apply(&values)[0]);
+
+        let mut t = Transform::identity(3, 3);
+        assert!(t.swap_bands(0, 2));
+        assert_eq!(values[6 * 9..7 * 9], t.apply(&values)[..9]);
+        assert!(!t.swap_bands(0, 3));
+        assert!(t.swap_rows(0, 2));
+        assert!(!t.swap_rows(2, 3));
+        assert!(t.swap_stacks(1, 2));
+        assert!(!t.swap_columns(0, 5));
+
+        for kind in (0..8).map(|v| TransformKind::from_u32(v).unwrap()) {
+            let mut t = Transform::identity(3, 3);
+            assert!(t.apply_kind(kind, 0, 1));
+            assert!(is_valid(&t.apply(&values)), "{:?}", kind);
+        }
+        assert_eq!(Some(TransformKind::SwapColumns), TransformKind::from_u32(7));
+        assert_eq!(None, TransformKind::from_u32(8));
+    }
+
+    #[test]
+    fn test_digits() {
+        let mut t = Transform::identity(3, 3);
+        assert!(!t.permute_digits(&[1, 2, 3]));
+        assert!(!t.permute_digits(&[1, 1, 3, 4, 5, 6, 7, 8, 9]));
+        assert!(t.permute_digits(&[2, 1, 3, 4, 5, 6, 7, 8, 9]));
+        assert!(!t.keeps_digits());
+        assert_eq!(2, t.digit(1));
+        assert_eq!(0, t.digit(0));
+        assert_eq!(0b1100, t.mask(0b1010));
+        assert!(t.apply_masks(&[0b1010; 81]).iter().all(|&m| m == 0b1100));
+    }
+
+    #[test]
+    fn test_properties() {
+        let mut t = Transform::identity(3, 3);
+        t.rotate();
+        t.reflect_vertical();
+        assert!(t.is_geometric());
+        assert!(t.keeps_diagonals());
+
+        let mut t = Transform::identity(3, 3);
+        t.swap_rows(0, 1);
+        assert!(!t.is_geometric());
+        assert!(!t.keeps_diagonals());
+
+        // 6x6 的宫不是正方形
+        let mut t = Transform::identity(2, 3);
+        assert!(!t.transpose());
+        assert!(!t.rotate());
+        t.reflect_horizontal();
+        assert!(t.is_geometric());
+    }
+
+    #[test]
+    fn test_random() {
+        let values = parse(SOLUTION);
+        let canonical = canonical_form(&values, 3, 3);
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            let t = Transform::random(&mut rng, 3, 3);
+            let transformed = t.apply(&values);
+            assert!(is_valid(&transformed));
+            assert_eq!(canonical, canonical_form(&transformed, 3, 3));
+        }
+    }
+}
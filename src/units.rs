@@ -0,0 +1,230 @@
+use std::sync::{Arc, OnceLock};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitKind {
+    Row,
+    Column,
+    Box,
+    Diagonal,
+    Region,
+}
+
+// 一组必须包含所有数字且不重复的格子
+#[derive(Clone, Debug)]
+pub struct Unit {
+    kind: UnitKind,
+    cells: Vec<usize>,
+}
+
+impl Unit {
+    pub fn new(kind: UnitKind, cells: Vec<usize>) -> Self {
+        Self { kind, cells }
+    }
+
+    pub fn kind(&self) -> UnitKind {
+        self.kind
+    }
+
+    pub fn cells(&self) -> &[usize] {
+        &self.cells
+    }
+}
+
+// 预先计算好的 unit/peer 表, 所有约束检查都基于此表
+#[derive(Debug)]
+pub struct Units {
+    size: usize,
+    units: Vec<Unit>,
+    cell_units: Vec<Vec<usize>>,
+    peers: Vec<Vec<usize>>,
+}
+
+impl Units {
+    // 标准 9x9 的表只计算一次
+    pub fn standard() -> Arc<Units> {
+        static STANDARD: OnceLock<Arc<Units>> = OnceLock::new();
+
+        STANDARD.get_or_init(|| Arc::new(Units::new(9, Self::grid_units(9, 3, 3)))).clone()
+    }
+
+    // size x size 的盘面, 宫为 box_rows 行 box_columns 列
+    pub fn grid(size: usize, box_rows: usize, box_columns: usize) -> Arc<Units> {
+        if (size, box_rows, box_columns) == (9, 3, 3) {
+            return Self::standard();
+        }
+
+        Arc::new(Units::new(size, Self::grid_units(size, box_rows, box_columns)))
+    }
+
+    // 标准规则外加两条主对角线
+    pub fn diagonal(size: usize, box_rows: usize, box_columns: usize) -> Arc<Units> {
+        static DIAGONAL: OnceLock<Arc<Units>> = OnceLock::new();
+
+        let build = || {
+            let mut units = Self::grid_units(size, box_rows, box_columns);
+            units.push(Unit::new(UnitKind::Diagonal, (0..size).map(|i| i * size + i).collect()));
+            units.push(Unit::new(UnitKind::Diagonal, (0..size).map(|i| i * size + size - 1 - i).collect()));
+
+            Arc::new(Units::new(size, units))
+        };
+
+        if (size, box_rows, box_columns) == (9, 3, 3) {
+            DIAGONAL.get_or_init(build).clone()
+        } else {
+            build()
+        }
+    }
+
+    // 行、列加上任意形状的区域, regions 为每个格子的区域编号
+    pub fn from_regions(size: usize, regions: &[u8]) -> Units {
+        let mut units: Vec<Unit> = Self::grid_units(size, 1, size).into_iter()
+            .filter(|u| u.kind() != UnitKind::Box)
+            .collect();
+
+        for region in 0..size as u8 {
+            let cells = (0..size * size).filter(|&i| regions[i] == region).collect();
+            units.push(Unit::new(UnitKind::Region, cells));
+        }
+
+        Units::new(size, units)
+    }
+
+    pub fn grid_units(size: usize, box_rows: usize, box_columns: usize) -> Vec<Unit> {
+        debug_assert_eq!(size, box_rows * box_columns);
+
+        let mut units = vec![];
+
+        for row in 0..size {
+            units.push(Unit::new(UnitKind::Row, (0..size).map(|c| row * size + c).collect()));
+        }
+
+        for column in 0..size {
+            units.push(Unit::new(UnitKind::Column, (0..size).map(|r| r * size + column).collect()));
+        }
+
+        for block in 0..size {
+            let br = (block / (size / box_columns)) * box_rows;
+            let bc = (block % (size / box_columns)) * box_columns;
+            let cells = (0..size).map(|i| (br + i / box_columns) * size + bc + i % box_columns).collect();
+            units.push(Unit::new(UnitKind::Box, cells));
+        }
+
+        units
+    }
+
+    pub fn new(size: usize, units: Vec<Unit>) -> Self {
+        let cell_count = size * size;
+        let mut cell_units = vec![vec![]; cell_count];
+        let mut peers = vec![vec![]; cell_count];
+
+        for (idx, unit) in units.iter().enumerate() {
+            debug_assert!(unit.cells.len() <= size);
+
+            for &cell in unit.cells.iter() {
+                cell_units[cell].push(idx);
+                peers[cell].extend(unit.cells.iter().filter(|&&x| x != cell));
+            }
+        }
+
+        for p in peers.iter_mut() {
+            p.sort_unstable();
+            p.dedup();
+        }
+
+        Self { size, units, cell_units, peers }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.size * self.size
+    }
+
+    pub fn units(&self) -> &[Unit] {
+        &self.units
+    }
+
+    pub fn unit(&self, idx: usize) -> &Unit {
+        &self.units[idx]
+    }
+
+    // 格子所在的所有 unit 下标
+    pub fn units_of(&self, cell: usize) -> &[usize] {
+        &self.cell_units[cell]
+    }
+
+    // 与格子同处任一 unit 的其它格子, 升序
+    pub fn peers(&self, cell: usize) -> &[usize] {
+        &self.peers[cell]
+    }
+
+    pub fn is_peer(&self, a: usize, b: usize) -> bool {
+        self.peers[a].binary_search(&b).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::units::*;
+
+    #[test]
+    fn test_standard_units() {
+        let units = Units::standard();
+        assert_eq!(27, units.units().len());
+
+        for cell in 0..81 {
+            assert_eq!(3, units.units_of(cell).len());
+            assert_eq!(20, units.peers(cell).len());
+            assert!(!units.peers(cell).contains(&cell));
+        }
+
+        assert!(!units.is_peer(0, 80));
+        assert!(units.is_peer(0, 20));
+        assert!(Arc::ptr_eq(&units, &Units::standard()));
+    }
+
+    #[test]
+    fn test_extra_units() {
+        let mut all = Units::grid_units(9, 3, 3);
+        all.push(Unit::new(UnitKind::Diagonal, (0..9).map(|i| i * 10).collect()));
+        let units = Units::new(9, all);
+
+        assert_eq!(4, units.units_of(0).len());
+        assert_eq!(26, units.peers(0).len());
+        assert!(units.is_peer(0, 80));
+        assert_eq!(20, units.peers(1).len());
+    }
+
+    #[test]
+    fn test_region_units() {
+        let mut regions = crate::region::box_regions(9, 3, 3);
+        regions.swap(2, 3);
+        let units = Units::from_regions(9, &regions);
+
+        assert_eq!(UnitKind::Region, units.unit(units.units_of(3)[2]).kind());
+        assert!(units.is_peer(3, 0));
+        assert!(units.is_peer(3, 19));
+        assert!(!units.is_peer(3, 13));
+        assert!(units.is_peer(2, 12));
+    }
+
+    #[test]
+    fn test_grid_units() {
+        // 6x6, 宫为 2 行 3 列
+        let units = Units::grid(6, 2, 3);
+        assert_eq!(18, units.units().len());
+        assert_eq!(36, units.cell_count());
+        assert_eq!(5 + 5 + 2, units.peers(0).len());
+        assert!(units.is_peer(0, 8));
+        assert!(!units.is_peer(0, 12 + 3));
+        assert!(!units.is_peer(0, 3 + 6));
+
+        let units = Units::diagonal(4, 2, 2);
+        assert!(units.is_peer(0, 15));
+        assert!(units.is_peer(3, 12));
+        assert!(!units.is_peer(1, 14));
+        assert!(Arc::ptr_eq(&Units::diagonal(9, 3, 3), &Units::diagonal(9, 3, 3)));
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+use crate::region;
+use crate::units::Units;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+pub enum Variant {
+    #[default]
+    Standard,
+    // X-Sudoku, 两条主对角线也必须包含所有数字
+    Diagonal,
+    // 宫被替换为任意形状的连通区域
+    Jigsaw,
+    // 杀手数独, 笼子内数字不重复且和为指定值
+    Killer,
+}
+
+impl Variant {
+    // C 接口传入的值, 无效时返回 None
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Variant::Standard),
+            1 => Some(Variant::Diagonal),
+            2 => Some(Variant::Jigsaw),
+            3 => Some(Variant::Killer),
+            _ => None,
+        }
+    }
+
+    pub fn units(self, size: usize, box_rows: usize, box_columns: usize) -> Arc<Units> {
+        match self {
+            Variant::Standard | Variant::Killer => Units::grid(size, box_rows, box_columns),
+            Variant::Diagonal => Units::diagonal(size, box_rows, box_columns),
+            // 初始区域为标准宫, 生成时会重新划分
+            Variant::Jigsaw => {
+                let regions = region::box_regions(size, box_rows, box_columns);
+                Arc::new(Units::from_regions(size, &regions))
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::os::raw::c_void;
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+use crate::board::Board;
+use crate::cell::HighlightMode;
+use crate::daily::Date;
+use crate::difficulty::Difficulty;
+use crate::variant::Variant;
+
+// 浏览器中使用的接口, 与 lib.rs 中的 C 接口对应
+#[wasm_bindgen(js_name = Sudoku)]
+pub struct WasmBoard {
+    board: Board,
+    // 格子更新的回调, 地址作为 callback_ptr 传给 Board
+    on_update: Option<Box<Function>>,
+}
+
+// 把 Board 的更新通知转发给 JS 函数 (row, column)
+extern "C" fn emit_update(ptr: *mut c_void, row: u32, column: u32) {
+    let callback = unsafe { &*(ptr as *const Function) };
+    let _ = callback.call2(&JsValue::NULL, &row.into(), &column.into());
+}
+
+#[wasm_bindgen(js_class = Sudoku)]
+impl WasmBoard {
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: Variant) -> Self {
+        Self::wrap(Board::new(variant))
+    }
+
+    // 不支持的尺寸返回 undefined
+    #[wasm_bindgen(js_name = withSize)]
+    pub fn with_size(size: u32, variant: Variant) -> Option<WasmBoard> {
+        Board::with_size(size as usize, variant).map(Self::wrap)
+    }
+
+    // 日期无效时返回 undefined
+    pub fn daily(year: u32, month: u32, day: u32, difficulty: Difficulty) -> Option<WasmBoard> {
+        Date::new(year, month, day).map(|date| Self::wrap(Board::daily(date, difficulty)))
+    }
+
+    // 存档无效时返回 undefined
+    pub fn restore(data: &str) -> Option<WasmBoard> {
+        Board::from_save(data).map(Self::wrap)
+    }
+
+    fn wrap(board: Board) -> Self {
+        Self { board, on_update: None }
+    }
+
+    fn contains(&self, row: u32, column: u32) -> bool {
+        let size = self.board.size() as u32;
+        row < size && column < size
+    }
+
+    // 超出盘面时返回 None, 在 JS 中为 undefined
+    fn cell_value<T>(&self, row: u32, column: u32, f: impl FnOnce(&Board, usize, usize) -> T) -> Option<T> {
+        self.contains(row, column).then(|| f(&self.board, row as usize, column as usize))
+    }
+
+    pub fn size(&self) -> u32 {
+        self.board.size() as u32
+    }
+
+    #[wasm_bindgen(js_name = boxRows)]
+    pub fn box_rows(&self) -> u32 {
+        self.board.box_shape().0 as u32
+    }
+
+    #[wasm_bindgen(js_name = boxColumns)]
+    pub fn box_columns(&self) -> u32 {
+        self.board.box_shape().1 as u32
+    }
+
+    #[wasm_bindgen(js_name = setDifficulty)]
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.board.set_difficulty(difficulty);
+    }
+
+    pub fn generate(&mut self) -> bool {
+        self.board.generate()
+    }
+
+    pub fn load(&mut self, puzzle: &str) -> bool {
+        self.board.load(puzzle)
+    }
+
+    pub fn export(&self) -> String {
+        self.board.export()
+    }
+
+    pub fn save(&self) -> String {
+        self.board.save()
+    }
+
+    // 空格返回 0, 超出盘面返回 undefined
+    pub fn get(&self, row: u32, column: u32) -> Option<u8> {
+        self.cell_value(row, column, |board, r, c| board.cell(r, c).selected().unwrap_or(0))
+    }
+
+    // val 为 0 时清除, 返回本次输入是否算作错误. 位置或数字无效时不修改
+    pub fn set(&mut self, row: u32, column: u32, val: u8) -> bool {
+        if !self.contains(row, column) || val as usize > self.board.size() {
+            return false;
+        }
+
+        self.board.enter(row as usize, column as usize, Some(val).filter(|&v| v != 0))
+    }
+
+    // 候选数位掩码, 第 n 位表示数字 n
+    pub fn candidates(&self, row: u32, column: u32) -> Option<u32> {
+        self.cell_value(row, column, |board, r, c| board.candidates(r, c))
+    }
+
+    pub fn notes(&self, row: u32, column: u32) -> Option<u32> {
+        self.cell_value(row, column, |board, r, c| board.cell(r, c).notes())
+    }
+
+    #[wasm_bindgen(js_name = toggleNote)]
+    pub fn toggle_note(&mut self, row: u32, column: u32, val: u8) -> bool {
+        if !self.contains(row, column) || val == 0 || val as usize > self.board.size() {
+            return false;
+        }
+
+        self.board.toggle_note(row as usize, column as usize, val)
+    }
+
+    // CellStates 的位
+    pub fn state(&self, row: u32, column: u32) -> Option<u32> {
+        self.cell_value(row, column, |board, r, c| board.cell(r, c).states().bits())
+    }
+
+    pub fn hint(&mut self) -> bool {
+        self.board.hint().is_some()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.board.undo()
+    }
+
+    #[wasm_bindgen(js_name = isSolved)]
+    pub fn is_solved(&self) -> bool {
+        self.board.is_solved()
+    }
+
+    // digit 为 0 表示不指定数字
+    #[wasm_bindgen(js_name = setHighlight)]
+    pub fn set_highlight(&mut self, mode: HighlightMode, digit: u8) {
+        self.board.set_highlight(mode, Some(digit).filter(|&d| d != 0));
+    }
+
+    #[wasm_bindgen(js_name = highlightMode)]
+    pub fn highlight_mode(&self) -> HighlightMode {
+        self.board.highlight_mode()
+    }
+
+    pub fn select(&mut self, row: u32, column: u32) {
+        self.board.select(Some((row as usize, column as usize)));
+    }
+
+    #[wasm_bindgen(js_name = clearSelection)]
+    pub fn clear_selection(&mut self) {
+        self.board.select(None);
+    }
+
+    pub fn pause(&mut self) {
+        self.board.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.board.resume();
+    }
+
+    #[wasm_bindgen(js_name = elapsedMs)]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.board.elapsed_ms() as f64
+    }
+
+    // 格子需要重绘时调用 callback(row, column)
+    #[wasm_bindgen(js_name = onUpdate)]
+    pub fn on_update(&mut self, callback: Function) {
+        let callback = Box::new(callback);
+        self.board.set_callback_ptr(&*callback as *const Function as *mut c_void);
+        self.board.set_update_callback(emit_update);
+        self.on_update = Some(callback);
+    }
+}
+
+// 需要 wasm32 目标, 用 `wasm-pack test --node -- --features wasm` 运行
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    use crate::wasm::*;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[wasm_bindgen_test]
+    fn test_play() {
+        let mut board = WasmBoard::new(Variant::Standard);
+        assert!(board.load(PUZZLE));
+        assert_eq!(Some(5), board.get(0, 0));
+        assert_eq!(None, board.get(9, 0));
+        assert_eq!(None, board.state(0, 9));
+        assert!(!board.set(9, 9, 1));
+        assert!(!board.set(0, 2, 10));
+        assert!(!board.toggle_note(0, 9, 1));
+        assert_eq!(PUZZLE, board.export());
+
+        let updates = Rc::new(RefCell::new(vec![]));
+        let sink = updates.clone();
+        let callback = Closure::<dyn FnMut(u32, u32)>::new(move |row, column| sink.borrow_mut().push((row, column)));
+        board.on_update(callback.as_ref().unchecked_ref::<Function>().clone());
+
+        assert!(board.candidates(0, 2).unwrap() & (1 << 4) != 0);
+        assert!(!board.set(0, 2, 4));
+        assert_eq!(Some(4), board.get(0, 2));
+        assert!(updates.borrow().contains(&(0, 2)));
+        assert!(board.undo());
+        assert_eq!(Some(0), board.get(0, 2));
+
+        board.select(1, 1);
+        board.set_highlight(HighlightMode::Peers, 0);
+        assert_eq!(HighlightMode::Peers, board.highlight_mode());
+        assert!(board.state(1, 2).unwrap() != 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_generate() {
+        let mut board = WasmBoard::new(Variant::Standard);
+        board.set_difficulty(Difficulty::Easy);
+        assert!(board.generate());
+        assert_eq!(81, board.export().len());
+        assert!(WasmBoard::with_size(7, Variant::Standard).is_none());
+        assert!(WasmBoard::restore(&board.save()).is_some());
+    }
+}